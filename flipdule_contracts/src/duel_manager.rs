@@ -6,18 +6,23 @@ use odra::prelude::*;
 use odra::casper_types::{U512, U256, PublicKey};
 use odra::ContractRef;
 use crate::liquid_stake::LiquidStakeContractRef;
+use crate::trading_engine::{FlipDuelTradingEngineContractRef, LeaderboardEntry};
 
 #[odra::module]
 pub struct FlipDuelManager {
     duels: Mapping<u64, Duel>,
     active_duels: List<u64>,
-    user_duels: Mapping<Address, List<u64>>,
+    user_duels: Mapping<(Address, u64), u64>,
+    user_duel_count: Mapping<Address, u64>,
     next_duel_id: Var<u64>,
     trading_engine: Var<Address>,
     staking_contract: Var<Address>,
     default_validator: Var<Option<PublicKey>>,
     owner: Var<Address>,
     platform_fee_percentage: Var<u8>,
+    virtual_bankroll: Var<U512>,
+    tie_break_policy: Var<TieBreakPolicy>,
+    rewards_claimed: Mapping<(u64, Address), bool>,
     total_duels_created: Var<u64>,
     total_prize_distributed: Var<U512>,
 }
@@ -38,8 +43,23 @@ pub struct Duel {
     pub status: DuelStatus,
     pub nft_collection: String,
     pub max_participants: u8,
+    pub join_deadline: Option<u64>,
     pub winner: Option<Address>,
-    pub claimed: bool,
+    pub winners: Vec<Address>,
+    pub tie_break: TieBreakPolicy,
+    pub payout_table: Vec<u32>,
+    pub allocations: Vec<(Address, U512)>,
+    pub platform_fee: U512,
+    pub withdrawal_claimed: bool,
+    pub stakes: Vec<(Address, U256)>,
+    pub departures: Vec<Departure>,
+}
+
+#[odra::odra_type]
+pub struct Departure {
+    pub player: Address,
+    pub withdrawal_request_id: u64,
+    pub refund_claimed: bool,
 }
 
 #[odra::odra_type]
@@ -48,10 +68,18 @@ pub enum DuelStatus {
     Active,            // Trading in progress
     Closed,            // Completed, winner determined, withdrawal requested
     WithdrawalPending, // Waiting for unbonding period
-    Completed,         // Winner claimed rewards
+    Completed,         // All ranked players claimed rewards
     Cancelled,         // Cancelled before starting
 }
 
+#[odra::odra_type]
+pub enum TieBreakPolicy {
+    EarliestFinalValue, // Tied player whose last trade came first wins
+    FewestTrades,       // Tied player with the fewest trades wins
+    SplitPot,           // All tied players share the prize evenly
+    JoinOrder,          // Tied player who joined first wins
+}
+
 #[odra::module]
 impl FlipDuelManager {
     /// Initialize the FlipDuel contract
@@ -60,6 +88,7 @@ impl FlipDuelManager {
         self.owner.set(caller);
         self.next_duel_id.set(1);
         self.platform_fee_percentage.set(5); // 5% platform fee
+        self.tie_break_policy.set(TieBreakPolicy::JoinOrder);
         self.total_duels_created.set(0);
         self.total_prize_distributed.set(U512::zero());
     }
@@ -94,17 +123,47 @@ impl FlipDuelManager {
         self.default_validator.set(Some(validator));
     }
 
+    /// Set virtual trading bankroll for new portfolios, zero uses entry fee (owner only)
+    pub fn set_virtual_bankroll(&mut self, bankroll: U512) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.virtual_bankroll.set(bankroll);
+    }
+
+    /// Set tie-break policy applied to newly created duels (owner only)
+    pub fn set_tie_break_policy(&mut self, policy: TieBreakPolicy) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.tie_break_policy.set(policy);
+    }
+
     /// Create a new trading duel with staked entry fee
+    /// Payout table lists prize percentages per rank (e.g. 60/30/10), empty means winner takes all;
+    /// if fewer players rank than the table pays, the used ranks are rescaled to the whole prize
+    /// With a join window (up to a week), anyone may cancel the duel if it is still Open after the deadline
+    /// The attached CSPR must equal the entry fee and is staked in LiquidStake
+    #[odra(payable)]
     pub fn create_duel(
         &mut self,
         duration_seconds: u64,
         nft_collection: String,
         max_participants: u8,
         entry_fee: U512,
+        payout_table: Vec<u32>,
+        join_window_seconds: Option<u64>,
     ) -> u64 {
         if entry_fee == U512::zero() {
             self.env().revert(Error::InvalidEntryFee);
         }
+        if self.env().attached_value() != entry_fee {
+            self.env().revert(Error::IncorrectFee);
+        }
         if duration_seconds < 60 {
             self.env().revert(Error::InvalidDuration);
         }
@@ -118,6 +177,23 @@ impl FlipDuelManager {
             self.env().revert(Error::InvalidParticipantCount);
         }
 
+        // Join windows run from one second up to a week
+        if let Some(window) = join_window_seconds {
+            if window == 0 || window > 7 * 24 * 60 * 60 {
+                self.env().revert(Error::InvalidJoinWindow);
+            }
+        }
+
+        let payout_table = if payout_table.is_empty() { vec![100] } else { payout_table };
+        if !is_valid_payout_table(&payout_table, max_participants) {
+            self.env().revert(Error::InvalidPayoutTable);
+        }
+
+        // Duels only trade NFTs the engine lists for their collection
+        if self.trading_engine_ref().get_collection_nfts(nft_collection.clone()).is_empty() {
+            self.env().revert(Error::UnknownCollection);
+        }
+
         let staking_addr = self.staking_contract.get();
         if staking_addr.is_none() {
             self.env().revert(Error::StakingContractNotSet);
@@ -131,8 +207,8 @@ impl FlipDuelManager {
         let duel_id = self.next_duel_id.get_or_default();
         let creator = self.env().caller();
 
-        // Stake entry fee in LiquidStake (without balance verification)
-        let mut staking_contract = LiquidStakeContractRef::new(self.env(), staking_addr.unwrap());
+        // Forward attached entry fee to LiquidStake
+        let mut staking_contract = LiquidStakeContractRef::new(self.env(), staking_addr.unwrap()).with_tokens(entry_fee);
         let stcspr_minted = staking_contract.stake(validator.unwrap(), entry_fee);
 
         let mut participants = Vec::new();
@@ -153,13 +229,22 @@ impl FlipDuelManager {
             status: DuelStatus::Open,
             nft_collection,
             max_participants,
+            join_deadline: join_window_seconds.map(|window| self.env().get_block_time() + window * 1000), // Convert to ms
             winner: None,
-            claimed: false,
+            winners: Vec::new(),
+            tie_break: self.get_tie_break_policy(),
+            payout_table,
+            allocations: Vec::new(),
+            platform_fee: U512::zero(),
+            withdrawal_claimed: false,
+            stakes: vec![(creator, stcspr_minted)],
+            departures: Vec::new(),
         };
 
         let duel_clone = duel.clone();
         self.duels.set(&duel_id, duel_clone);
         self.active_duels.push(duel_id);
+        self.index_user_duel(creator, duel_id);
 
         self.next_duel_id.set(duel_id + 1);
         let total = self.total_duels_created.get_or_default();
@@ -176,7 +261,8 @@ impl FlipDuelManager {
         duel_id
     }
 
-    /// Join an existing duel with staked entry fee
+    /// Join an existing duel with staked entry fee (attached CSPR must equal the entry fee)
+    #[odra(payable)]
     pub fn join_duel(&mut self, duel_id: u64) {
         let duel_opt = self.duels.get(&duel_id);
         if duel_opt.is_none() {
@@ -189,23 +275,35 @@ impl FlipDuelManager {
         if !matches!(duel.status, DuelStatus::Open) {
             self.env().revert(Error::DuelNotOpen);
         }
+        if self.env().attached_value() != entry_fee {
+            self.env().revert(Error::IncorrectFee);
+        }
         if duel.participants.contains(&caller) {
             self.env().revert(Error::AlreadyParticipant);
         }
+        // Players who left keep their own withdrawal and cannot rejoin the same duel
+        if duel.departures.iter().any(|departure| departure.player == caller) {
+            self.env().revert(Error::AlreadyLeft);
+        }
         if duel.participants.len() >= duel.max_participants as usize {
             self.env().revert(Error::DuelFull);
         }
+        if duel.join_deadline.is_some_and(|deadline| self.env().get_block_time() >= deadline) {
+            self.env().revert(Error::JoinDeadlinePassed);
+        }
 
         let staking_addr = self.staking_contract.get().unwrap_or_else(|| self.env().revert(Error::StakingContractNotSet));
         let validator = self.default_validator.get().unwrap_or_else(|| self.env().revert(Error::ValidatorNotSet)).unwrap_or_else(|| self.env().revert(Error::ValidatorNotSet));
 
-        // Stake additional entry fee in LiquidStake (without balance verification)
-        let mut staking_contract = LiquidStakeContractRef::new(self.env(), staking_addr);
+        // Forward attached entry fee to LiquidStake
+        let mut staking_contract = LiquidStakeContractRef::new(self.env(), staking_addr).with_tokens(entry_fee);
         let stcspr_minted = staking_contract.stake(validator, entry_fee);
 
         duel.participants.push(caller);
+        self.index_user_duel(caller, duel_id);
         duel.prize_pool = duel.prize_pool + entry_fee;
         duel.stcspr_staked = duel.stcspr_staked + stcspr_minted;
+        duel.stakes.push((caller, stcspr_minted));
 
         // Auto-start if max participants reached
         if duel.participants.len() == duel.max_participants as usize {
@@ -222,6 +320,92 @@ impl FlipDuelManager {
         });
     }
 
+    /// Leave an Open duel and request unstake of the stCSPR minted for the caller (creator must cancel instead)
+    pub fn leave_duel(&mut self, duel_id: u64) -> u64 {
+        let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
+        let caller = self.env().caller();
+
+        if !matches!(duel.status, DuelStatus::Open) {
+            self.env().revert(Error::DuelNotOpen);
+        }
+        if caller == duel.creator {
+            self.env().revert(Error::CreatorCannotLeave);
+        }
+        let index = duel
+            .participants
+            .iter()
+            .position(|player| *player == caller)
+            .unwrap_or_else(|| self.env().revert(Error::NotParticipant));
+
+        // Request unstake of exactly the stCSPR minted for the leaver's entry fee
+        let stake_index = duel
+            .stakes
+            .iter()
+            .position(|(player, _)| *player == caller)
+            .unwrap_or_else(|| self.env().revert(Error::NotParticipant));
+        let (_, stcspr_share) = duel.stakes.remove(stake_index);
+        let staking_addr = self.staking_contract.get().unwrap();
+        let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
+        let request_id = staking_contract.request_unstake(u256_to_u512(stcspr_share));
+
+        duel.participants.remove(index);
+        duel.prize_pool -= duel.entry_fee;
+        duel.stcspr_staked -= stcspr_share;
+        duel.departures.push(Departure {
+            player: caller,
+            withdrawal_request_id: request_id,
+            refund_claimed: false,
+        });
+        let duel_clone = duel.clone();
+        self.duels.set(&duel_id, duel_clone);
+
+        self.env().emit_event(PlayerLeft {
+            duel_id,
+            player: caller,
+            stcspr_amount: stcspr_share,
+            withdrawal_request_id: request_id,
+        });
+
+        request_id
+    }
+
+    /// Claim the unstaked entry fee after leaving a duel, once unbonding has passed
+    pub fn claim_leave_refund(&mut self, duel_id: u64) {
+        let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
+        let caller = self.env().caller();
+
+        // Leave refunds are tracked on the departure, apart from prize and cancellation claims
+        let departure = duel
+            .departures
+            .iter_mut()
+            .find(|departure| departure.player == caller)
+            .unwrap_or_else(|| self.env().revert(Error::NotParticipant));
+        if departure.refund_claimed {
+            self.env().revert(Error::AlreadyClaimed);
+        }
+        let request_id = departure.withdrawal_request_id;
+
+        let staking_addr = self.staking_contract.get().unwrap();
+        let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
+        if !staking_contract.is_withdrawal_ready(request_id) {
+            self.env().revert(Error::WithdrawalNotReady);
+        }
+
+        // Claim from LiquidStake - CSPR comes to this contract, then goes to the leaver
+        let amount = staking_contract.get_withdrawal_amount(request_id);
+        staking_contract.claim(request_id);
+        departure.refund_claimed = true;
+        self.duels.set(&duel_id, duel);
+
+        self.env().transfer_tokens(&caller, &amount);
+
+        self.env().emit_event(RefundClaimed {
+            duel_id,
+            player: caller,
+            amount,
+        });
+    }
+
     /// Manually start a duel (if minimum players met)
     pub fn start_duel(&mut self, duel_id: u64) {
         let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
@@ -243,16 +427,21 @@ impl FlipDuelManager {
     }
 
     fn start_duel_internal(&self, duel: &mut Duel) {
+        let mut trading_engine = self.trading_engine_ref();
+
         let current_time = self.env().get_block_time();
         duel.start_time = current_time;
         duel.end_time = current_time + (duel.duration_seconds * 1000); // Convert to ms
         duel.status = DuelStatus::Active;
 
+        trading_engine.register_duel_nfts(duel.id, duel.nft_collection.clone(), duel.end_time);
+
         // Initialize trading portfolios for all participants
-        let _starting_balance = duel.entry_fee;
-        
-        // Note: In production, call trading engine to initialize each portfolio
-        // For now, this is a placeholder for the integration point
+        let bankroll = self.virtual_bankroll.get_or_default();
+        let starting_balance = if bankroll == U512::zero() { duel.entry_fee } else { bankroll };
+        for participant in &duel.participants {
+            trading_engine.initialize_portfolio(duel.id, *participant, starting_balance);
+        }
 
         self.env().emit_event(DuelStarted {
             duel_id: duel.id,
@@ -274,16 +463,33 @@ impl FlipDuelManager {
             self.env().revert(Error::DuelNotEnded);
         }
 
-        // Calculate winner (highest gain percentage)
-        // In production: Query TradingEngine for each participant's gain
-        let mut max_gain: i32 = i32::MIN;
-        let mut winner: Option<Address> = None;
-
-        // TODO: Replace with actual trading engine query
-        // For now, first participant wins (placeholder)
-        if !duel.participants.is_empty() {
-            winner = Some(duel.participants[0]);
-            max_gain = 0;
+        // Calculate winner (highest portfolio value) from the TradingEngine leaderboard
+        let trading_engine = self.trading_engine_ref();
+        let leaderboard = trading_engine.get_leaderboard(duel_id, duel.participants.clone(), duel.end_time);
+
+        // Leaderboard is sorted by value (descending), equal values keep join order
+        let top = leaderboard
+            .first()
+            .cloned()
+            .unwrap_or_else(|| self.env().revert(Error::NoTradingResults));
+        let tied: Vec<LeaderboardEntry> = leaderboard
+            .iter()
+            .filter(|entry| entry.current_value == top.current_value)
+            .cloned()
+            .collect();
+        let winners = break_tie(&duel.tie_break, &tied);
+        let winner = winners[0];
+
+        // Final ranking: first place group, then the rest of the leaderboard
+        // grouped by equal value so players tied at any paid rank pool their slots
+        let mut ranking: Vec<Vec<Address>> = vec![winners.clone()];
+        let mut last_value = None;
+        for entry in leaderboard.iter().filter(|entry| !winners.contains(&entry.player)) {
+            match ranking.last_mut() {
+                Some(group) if last_value == Some(entry.current_value) => group.push(entry.player),
+                _ => ranking.push(vec![entry.player]),
+            }
+            last_value = Some(entry.current_value);
         }
 
         // Request unstake from LiquidStake
@@ -292,79 +498,80 @@ impl FlipDuelManager {
         let stcspr_amount = u256_to_u512(duel.stcspr_staked);
         let request_id = staking_contract.request_unstake(stcspr_amount);
 
-        duel.winner = winner;
+        // Split the withdrawn amount (minus platform fee) along the payout table
+        let withdrawal_amount = staking_contract.get_withdrawal_amount(request_id);
+        let platform_fee_pct = self.platform_fee_percentage.get_or_default();
+        let platform_fee = (withdrawal_amount * U512::from(platform_fee_pct)) / U512::from(100);
+        duel.allocations = allocate_prizes(&duel.payout_table, &ranking, withdrawal_amount - platform_fee);
+        duel.platform_fee = platform_fee;
+
+        duel.winner = Some(winner);
+        duel.winners = winners.clone();
         duel.withdrawal_request_id = Some(request_id);
         duel.withdrawal_requested_at = current_time;
         duel.status = DuelStatus::WithdrawalPending;
         let duel_clone = duel.clone();
         self.duels.set(&duel_id, duel_clone);
+        self.remove_active_duel(duel_id);
 
         self.env().emit_event(DuelClosed {
             duel_id,
-            winner: winner.unwrap(),
-            gain_percentage: max_gain,
+            winner,
+            winners,
+            gain_percentage: top.gain_percentage,
             prize_pool: duel.prize_pool,
+            tie_break: duel.tie_break,
         });
     }
 
-    /// Winner claims their prize after unbonding period
+    /// Ranked player claims their prize allocation after unbonding period
     pub fn claim_rewards(&mut self, duel_id: u64) {
         let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
         let caller = self.env().caller();
 
-        if !matches!(duel.status, DuelStatus::WithdrawalPending) {
+        if !matches!(duel.status, DuelStatus::WithdrawalPending | DuelStatus::Completed) {
             self.env().revert(Error::WithdrawalNotReady);
         }
-        if duel.winner != Some(caller) {
-            self.env().revert(Error::NotWinner);
-        }
-        if duel.claimed {
+        let amount = duel
+            .allocations
+            .iter()
+            .find(|(player, _)| *player == caller)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_else(|| self.env().revert(Error::NotWinner));
+        if self.rewards_claimed.get(&(duel_id, caller)).unwrap_or(false) {
             self.env().revert(Error::AlreadyClaimed);
         }
 
-        let request_id = duel.withdrawal_request_id.unwrap();
-
-        // Check if withdrawal is ready and claim from LiquidStake
-        let staking_addr = self.staking_contract.get().unwrap();
-        let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
-
-        if !staking_contract.is_withdrawal_ready(request_id) {
-            self.env().revert(Error::WithdrawalNotReady);
+        // First claimant pulls the whole withdrawal from LiquidStake into this contract
+        // Platform fee stays in contract (treasury)
+        let collected_now = !duel.withdrawal_claimed;
+        if collected_now {
+            self.collect_withdrawal(&mut duel);
         }
 
-        // Get balance before claim
-        let balance_before = self.env().self_balance();
-
-        // Claim from LiquidStake - CSPR comes to this contract
-        staking_contract.claim(request_id);
-
-        // Get balance after claim
-        let balance_after = self.env().self_balance();
-        let claimed_amount = balance_after - balance_before;
-
-        // Calculate platform fee and winner amount
-        let platform_fee_pct = self.platform_fee_percentage.get_or_default();
-        let platform_fee = (claimed_amount * U512::from(platform_fee_pct)) / U512::from(100);
-        let winner_amount = claimed_amount - platform_fee;
-
-        duel.claimed = true;
-        duel.status = DuelStatus::Completed;
+        self.rewards_claimed.set(&(duel_id, caller), true);
+        let all_claimed = duel
+            .allocations
+            .iter()
+            .all(|(player, _)| self.rewards_claimed.get(&(duel_id, *player)).unwrap_or(false));
+        if all_claimed {
+            duel.status = DuelStatus::Completed;
+        }
+        let platform_fee = if collected_now { duel.platform_fee } else { U512::zero() };
         let duel_clone = duel.clone();
         self.duels.set(&duel_id, duel_clone);
 
         // Update total distributed
         let total_distributed = self.total_prize_distributed.get_or_default();
-        self.total_prize_distributed.set(total_distributed + winner_amount);
+        self.total_prize_distributed.set(total_distributed + amount);
 
-        // Transfer prize to winner
-        self.env().transfer_tokens(&caller, &winner_amount);
-
-        // Platform fee stays in contract (treasury)
+        // Transfer prize share to the ranked player
+        self.env().transfer_tokens(&caller, &amount);
 
         self.env().emit_event(RewardsClaimed {
             duel_id,
             winner: caller,
-            amount: winner_amount,
+            amount,
             platform_fee,
         });
     }
@@ -384,25 +591,57 @@ impl FlipDuelManager {
             self.env().revert(Error::CannotCancel);
         }
 
+        self.cancel_duel_internal(&mut duel);
+        let duel_clone = duel.clone();
+        self.duels.set(&duel_id, duel_clone);
+        self.remove_active_duel(duel_id);
+
+        self.env().emit_event(DuelCancelled {
+            duel_id,
+            refunded_players: duel.participants.len() as u8,
+        });
+    }
+
+    /// Cancel an Open duel whose join deadline has passed (callable by anyone)
+    pub fn cancel_expired_duel(&mut self, duel_id: u64) {
+        let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
+
+        if !matches!(duel.status, DuelStatus::Open) {
+            self.env().revert(Error::DuelNotOpen);
+        }
+        match duel.join_deadline {
+            Some(deadline) if self.env().get_block_time() >= deadline => {}
+            _ => self.env().revert(Error::DuelNotExpired),
+        }
+
+        self.cancel_duel_internal(&mut duel);
+        let duel_clone = duel.clone();
+        self.duels.set(&duel_id, duel_clone);
+        self.remove_active_duel(duel_id);
+
+        self.env().emit_event(DuelCancelled {
+            duel_id,
+            refunded_players: duel.participants.len() as u8,
+        });
+    }
+
+    fn cancel_duel_internal(&self, duel: &mut Duel) {
         // Request unstake from LiquidStake
         let staking_addr = self.staking_contract.get().unwrap();
         let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
         let stcspr_amount = u256_to_u512(duel.stcspr_staked);
         let request_id = staking_contract.request_unstake(stcspr_amount);
 
+        // Every participant is owed an equal share of the withdrawal
+        let withdrawal_amount = staking_contract.get_withdrawal_amount(request_id);
+        duel.allocations = allocate_refunds(&duel.participants, withdrawal_amount);
+
         duel.withdrawal_request_id = Some(request_id);
         duel.withdrawal_requested_at = self.env().get_block_time();
         duel.status = DuelStatus::Cancelled;
-        let duel_clone = duel.clone();
-        self.duels.set(&duel_id, duel_clone);
-
-        self.env().emit_event(DuelCancelled {
-            duel_id,
-            refunded_players: duel.participants.len() as u8,
-        });
     }
 
-    /// Claim refund for cancelled duel after unbonding period
+    /// Participant claims their refund share for a cancelled duel after unbonding period
     pub fn claim_refund(&mut self, duel_id: u64) {
         let mut duel = self.duels.get(&duel_id).expect("FlipDuel: Duel not found");
         let caller = self.env().caller();
@@ -410,47 +649,31 @@ impl FlipDuelManager {
         if !matches!(duel.status, DuelStatus::Cancelled) {
             self.env().revert(Error::DuelNotCancelled);
         }
-        if !duel.participants.contains(&caller) {
-            self.env().revert(Error::NotParticipant);
-        }
-        if duel.claimed {
+        let amount = duel
+            .allocations
+            .iter()
+            .find(|(player, _)| *player == caller)
+            .map(|(_, amount)| *amount)
+            .unwrap_or_else(|| self.env().revert(Error::NotParticipant));
+        if self.rewards_claimed.get(&(duel_id, caller)).unwrap_or(false) {
             self.env().revert(Error::AlreadyClaimed);
         }
 
-        let request_id = duel.withdrawal_request_id.unwrap();
-
-        // Check if withdrawal is ready
-        let staking_addr = self.staking_contract.get().unwrap();
-        let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
-
-        if !staking_contract.is_withdrawal_ready(request_id) {
-            self.env().revert(Error::WithdrawalNotReady);
+        // First claimant pulls the whole withdrawal from LiquidStake into this contract
+        if !duel.withdrawal_claimed {
+            self.collect_withdrawal(&mut duel);
         }
 
-        // Get balance before claim
-        let balance_before = self.env().self_balance();
-
-        // Claim from LiquidStake
-        staking_contract.claim(request_id);
-
-        // Get balance after claim
-        let balance_after = self.env().self_balance();
-        let claimed_amount = balance_after - balance_before;
-
-        // Mark as claimed
-        duel.claimed = true;
+        self.rewards_claimed.set(&(duel_id, caller), true);
         let duel_clone = duel.clone();
         self.duels.set(&duel_id, duel_clone);
 
-        // Refund each participant proportionally
-        let refund_per_participant = claimed_amount / U512::from(duel.participants.len() as u64);
-        for participant in &duel.participants {
-            self.env().transfer_tokens(participant, &refund_per_participant);
-        }
+        self.env().transfer_tokens(&caller, &amount);
 
         self.env().emit_event(RefundClaimed {
             duel_id,
-            total_amount: claimed_amount,
+            player: caller,
+            amount,
         });
     }
 
@@ -461,25 +684,38 @@ impl FlipDuelManager {
         self.duels.get(&duel_id)
     }
 
-    /// Get all active duels (Open or Active status)
-    pub fn get_active_duels(&self) -> Vec<u64> {
-        let mut result = Vec::new();
-        for i in 0..self.active_duels.len() {
-            if let Some(duel_id) = self.active_duels.get(i) {
-                if let Some(duel) = self.duels.get(&duel_id) {
-                    if matches!(duel.status, DuelStatus::Open | DuelStatus::Active) {
-                        result.push(duel_id);
-                    }
-                }
-            }
-        }
-        result
+    /// Get a page of active duels (Open or Active status)
+    pub fn get_active_duels(&self, offset: u32, limit: u32) -> Vec<u64> {
+        let end = offset.saturating_add(limit).min(self.active_duels.len());
+        (offset..end)
+            .filter_map(|i| self.active_duels.get(i))
+            .filter(|duel_id| self.is_live_duel(*duel_id))
+            .collect()
+    }
+
+    /// Get number of entries in the active duel list
+    pub fn get_active_duel_count(&self) -> u32 {
+        self.active_duels.len()
     }
 
-    /// Get all duels for a specific user
-    pub fn get_user_duels(&self, _user: Address) -> Vec<u64> {
-        // User duels disabled - List in Mapping not supported
-        Vec::new()
+    /// Get a page of duels a user created or joined (oldest first)
+    /// Duels are filtered by status if given, offset and limit apply to the matching duels
+    pub fn get_user_duels(&self, user: Address, offset: u64, limit: u64, status: Option<DuelStatus>) -> Vec<u64> {
+        let count = self.user_duel_count.get(&user).unwrap_or(0);
+        (0..count)
+            .filter_map(|index| self.user_duels.get(&(user, index)))
+            .filter(|duel_id| match &status {
+                Some(status) => self.duels.get(duel_id).is_some_and(|duel| duel.status == *status),
+                None => true,
+            })
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /// Get number of duels a user created or joined
+    pub fn get_user_duel_count(&self, user: Address) -> u64 {
+        self.user_duel_count.get(&user).unwrap_or(0)
     }
 
     /// Get platform statistics
@@ -491,6 +727,52 @@ impl FlipDuelManager {
         }
     }
 
+    /// Check whether a player has claimed their prize, or their refund from a cancelled duel
+    pub fn has_claimed_rewards(&self, duel_id: u64, player: Address) -> bool {
+        self.rewards_claimed.get(&(duel_id, player)).unwrap_or(false)
+    }
+
+    /// Get tie-break policy applied to newly created duels
+    pub fn get_tie_break_policy(&self) -> TieBreakPolicy {
+        self.tie_break_policy.get().unwrap_or(TieBreakPolicy::JoinOrder)
+    }
+
+    /// Get virtual trading bankroll (zero means entry fee is used)
+    pub fn get_virtual_bankroll(&self) -> U512 {
+        self.virtual_bankroll.get_or_default()
+    }
+
+    /// Remove finished duels from a range of the active list (owner only)
+    /// Entries moved in by swap-remove are re-checked, so the range stays consistent
+    pub fn compact_active_duels(&mut self, offset: u32, limit: u32) -> u32 {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        let mut removed = 0u32;
+        let mut index = offset;
+        let mut checked = 0u32;
+        while checked < limit && index < self.active_duels.len() {
+            checked += 1;
+            let duel_id = self.active_duels.get(index).unwrap();
+            if self.is_live_duel(duel_id) {
+                index += 1;
+            } else {
+                self.swap_remove_active_duel(index);
+                removed += 1;
+            }
+        }
+
+        self.env().emit_event(ActiveDuelsCompacted {
+            removed,
+            remaining: self.active_duels.len(),
+        });
+
+        removed
+    }
+
     /// Update platform fee (admin only)
     pub fn set_platform_fee(&mut self, new_fee_percentage: u8) {
         if new_fee_percentage > 10 {
@@ -498,6 +780,54 @@ impl FlipDuelManager {
         }
         self.platform_fee_percentage.set(new_fee_percentage);
     }
+
+    // ============== INTERNAL HELPERS ==============
+
+    fn index_user_duel(&mut self, user: Address, duel_id: u64) {
+        let count = self.user_duel_count.get(&user).unwrap_or(0);
+        self.user_duels.set(&(user, count), duel_id);
+        self.user_duel_count.set(&user, count + 1);
+    }
+
+    fn collect_withdrawal(&self, duel: &mut Duel) {
+        let request_id = duel.withdrawal_request_id.unwrap();
+        let staking_addr = self.staking_contract.get().unwrap();
+        let mut staking_contract = LiquidStakeContractRef::new(self.env(),staking_addr);
+
+        if !staking_contract.is_withdrawal_ready(request_id) {
+            self.env().revert(Error::WithdrawalNotReady);
+        }
+
+        // Claim from LiquidStake - CSPR comes to this contract
+        staking_contract.claim(request_id);
+        duel.withdrawal_claimed = true;
+    }
+
+    fn is_live_duel(&self, duel_id: u64) -> bool {
+        self.duels
+            .get(&duel_id)
+            .is_some_and(|duel| matches!(duel.status, DuelStatus::Open | DuelStatus::Active))
+    }
+
+    fn remove_active_duel(&mut self, duel_id: u64) {
+        if let Some(index) = (0..self.active_duels.len()).find(|i| self.active_duels.get(*i) == Some(duel_id)) {
+            self.swap_remove_active_duel(index);
+        }
+    }
+
+    // Swap with last element and truncate
+    fn swap_remove_active_duel(&mut self, index: u32) {
+        if let Some(last_val) = self.active_duels.pop() {
+            if index < self.active_duels.len() {
+                self.active_duels.replace(index, last_val);
+            }
+        }
+    }
+
+    fn trading_engine_ref(&self) -> FlipDuelTradingEngineContractRef {
+        let engine_addr = self.trading_engine.get().unwrap_or_else(|| self.env().revert(Error::TradingEngineNotSet));
+        FlipDuelTradingEngineContractRef::new(self.env(), engine_addr)
+    }
 }
 
 #[odra::odra_type]
@@ -525,6 +855,14 @@ pub struct PlayerJoined {
     pub participants_count: u8,
 }
 
+#[odra::event]
+pub struct PlayerLeft {
+    pub duel_id: u64,
+    pub player: Address,
+    pub stcspr_amount: U256,
+    pub withdrawal_request_id: u64,
+}
+
 #[odra::event]
 pub struct DuelStarted {
     pub duel_id: u64,
@@ -537,8 +875,10 @@ pub struct DuelStarted {
 pub struct DuelClosed {
     pub duel_id: u64,
     pub winner: Address,
+    pub winners: Vec<Address>,
     pub gain_percentage: i32,
     pub prize_pool: U512,
+    pub tie_break: TieBreakPolicy,
 }
 
 #[odra::event]
@@ -555,10 +895,17 @@ pub struct DuelCancelled {
     pub refunded_players: u8,
 }
 
+#[odra::event]
+pub struct ActiveDuelsCompacted {
+    pub removed: u32,
+    pub remaining: u32,
+}
+
 #[odra::event]
 pub struct RefundClaimed {
     pub duel_id: u64,
-    pub total_amount: U512,
+    pub player: Address,
+    pub amount: U512,
 }
 
 #[odra::odra_error]
@@ -589,6 +936,85 @@ pub enum Error {
     InsufficientStakingLiquidity,
     DuelNotCancelled,
     NotParticipant,
+    TradingEngineNotSet,
+    NoTradingResults,
+    InvalidPayoutTable,
+    CreatorCannotLeave,
+    InvalidJoinWindow,
+    JoinDeadlinePassed,
+    DuelNotExpired,
+    UnknownCollection,
+    AlreadyLeft,
+}
+
+// Resolve players tied on the top gain into the winner list.
+// Entries arrive in join order, and min_by_key keeps the first of equal keys.
+fn break_tie(policy: &TieBreakPolicy, tied: &[LeaderboardEntry]) -> Vec<Address> {
+    let winner = match policy {
+        TieBreakPolicy::SplitPot => return tied.iter().map(|entry| entry.player).collect(),
+        TieBreakPolicy::JoinOrder => tied.first(),
+        TieBreakPolicy::FewestTrades => tied.iter().min_by_key(|entry| entry.trades_count),
+        TieBreakPolicy::EarliestFinalValue => tied.iter().min_by_key(|entry| entry.last_trade_time),
+    };
+    winner.map(|entry| vec![entry.player]).unwrap_or_default()
+}
+
+// Payout percentages must be non-zero, non-increasing, sum to 100
+// and not pay more ranks than the duel can hold.
+fn is_valid_payout_table(payout_table: &[u32], max_participants: u8) -> bool {
+    if payout_table.len() > max_participants as usize {
+        return false;
+    }
+    if payout_table.contains(&0) || payout_table.windows(2).any(|pair| pair[1] > pair[0]) {
+        return false;
+    }
+    payout_table.iter().sum::<u32>() == 100
+}
+
+// Allocate the prize along the payout table for the ranked groups.
+// With fewer ranked players than paid ranks, the used ranks are rescaled to the whole prize
+// (60/30/10 with two players pays 66.67/33.33).
+// Players in the same group are tied and share their combined slots evenly,
+// rounding dust goes to the first ranked player.
+fn allocate_prizes(payout_table: &[u32], ranking: &[Vec<Address>], prize: U512) -> Vec<(Address, U512)> {
+    let ranked = ranking.iter().map(Vec::len).sum::<usize>().min(payout_table.len());
+    let paid = &payout_table[..ranked];
+    let paid_pct = U512::from(paid.iter().sum::<u32>());
+    let slot = |rank: usize| -> U512 {
+        let pct = paid.get(rank).copied().unwrap_or(0);
+        (prize * U512::from(pct)) / paid_pct
+    };
+
+    let mut allocations: Vec<(Address, U512)> = Vec::new();
+    let mut rank = 0;
+    for group in ranking.iter().filter(|group| !group.is_empty()) {
+        let pooled = (rank..rank + group.len()).fold(U512::zero(), |acc, r| acc + slot(r));
+        let share = pooled / U512::from(group.len() as u64);
+        allocations.extend(group.iter().map(|player| (*player, share)));
+        rank += group.len();
+    }
+
+    let allocated = allocations.iter().fold(U512::zero(), |acc, (_, amount)| acc + *amount);
+    if let Some(first) = allocations.first_mut() {
+        first.1 += prize - allocated;
+    }
+    allocations.retain(|(_, amount)| *amount > U512::zero());
+    allocations
+}
+
+// Split a cancelled duel's withdrawal evenly, rounding dust goes to the creator.
+fn allocate_refunds(participants: &[Address], total: U512) -> Vec<(Address, U512)> {
+    if participants.is_empty() {
+        return Vec::new();
+    }
+    let count = U512::from(participants.len() as u64);
+    let share = total / count;
+    let dust = total - share * count;
+    participants
+        .iter()
+        .enumerate()
+        .map(|(i, player)| (*player, if i == 0 { share + dust } else { share }))
+        .collect()
 }
 
 // Helper function for U256 to U512 conversion
@@ -597,3 +1023,673 @@ fn u256_to_u512(value: U256) -> U512 {
     value.to_little_endian(&mut bytes);
     U512::from_little_endian(&bytes)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::liquid_stake::{LiquidStake, LiquidStakeHostRef, LiquidStakeInitArgs, DEFAULT_UNBONDING_PERIOD_MS};
+    use crate::price_oracle::FlipDuelPriceOracle;
+    use crate::trading_engine::{Error as TradingError, FlipDuelTradingEngine, FlipDuelTradingEngineHostRef};
+    use odra::host::{Deployer, HostEnv, HostRef, NoArgs};
+
+    const ENTRY_FEE: u64 = 10_000_000_000;
+
+    fn setup() -> (HostEnv, FlipDuelManagerHostRef, FlipDuelTradingEngineHostRef, LiquidStakeHostRef) {
+        let env = odra_test::env();
+        let deployer = env.get_account(0);
+
+        let mut staking = LiquidStake::deploy(&env, LiquidStakeInitArgs { owner: deployer });
+        staking.add_validator(env.get_validator(0));
+        let mut engine = FlipDuelTradingEngine::deploy(&env, NoArgs);
+        let mut manager = FlipDuelManager::deploy(&env, NoArgs);
+
+        // Deployer acts as the price oracle so tests can move prices directly
+        engine.set_price_oracle(deployer);
+        engine.set_collection_nfts("collection".to_string(), vec!["NFT1".to_string(), "NFT2".to_string()]);
+        engine.set_duel_manager(manager.address());
+        manager.set_trading_engine(engine.address());
+        manager.set_staking_contract(staking.address());
+        manager.set_validator(env.get_validator(0));
+        staking.set_duel_manager(manager.address());
+
+        (env, manager, engine, staking)
+    }
+
+    fn start_two_player_duel(env: &HostEnv, manager: &mut FlipDuelManagerHostRef) -> u64 {
+        env.set_caller(env.get_account(1));
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        env.set_caller(env.get_account(2));
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        duel_id
+    }
+
+    #[test]
+    fn start_initializes_portfolios_with_entry_fee() {
+        let (env, mut manager, engine, _) = setup();
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        assert!(matches!(manager.get_duel(duel_id).unwrap().status, DuelStatus::Active));
+        for player in [env.get_account(1), env.get_account(2)] {
+            let portfolio = engine.get_portfolio(duel_id, player).unwrap();
+            assert_eq!(portfolio.cspr_balance, U512::from(ENTRY_FEE));
+            assert_eq!(portfolio.initial_value, U512::from(ENTRY_FEE));
+        }
+    }
+
+    #[test]
+    fn start_uses_virtual_bankroll_when_set() {
+        let (env, mut manager, engine, _) = setup();
+        manager.set_virtual_bankroll(U512::from(1_000u64));
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        let portfolio = engine.get_portfolio(duel_id, env.get_account(2)).unwrap();
+        assert_eq!(portfolio.cspr_balance, U512::from(1_000u64));
+    }
+
+    #[test]
+    fn create_reverts_without_trading_engine() {
+        let env = odra_test::env();
+        let mut staking = LiquidStake::deploy(&env, LiquidStakeInitArgs { owner: env.get_account(0) });
+        staking.add_validator(env.get_validator(0));
+        let mut manager = FlipDuelManager::deploy(&env, NoArgs);
+        manager.set_staking_contract(staking.address());
+        manager.set_validator(env.get_validator(0));
+
+        // The collection allow-list lives in the engine, so duels cannot be created without it
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            manager
+                .with_tokens(U512::from(ENTRY_FEE))
+                .try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None),
+            Err(Error::TradingEngineNotSet.into())
+        );
+    }
+
+    #[test]
+    fn close_picks_highest_gain() {
+        let (env, mut manager, mut engine, _) = setup();
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(5_000_000_000u64));
+
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(10_000_000_000u64));
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winner, Some(env.get_account(2)));
+        assert!(env.emitted_event(
+            &manager,
+            DuelClosed {
+                duel_id,
+                winner: env.get_account(2),
+                winners: vec![env.get_account(2)],
+                gain_percentage: 50,
+                prize_pool: U512::from(2 * ENTRY_FEE),
+                tie_break: TieBreakPolicy::JoinOrder,
+            }
+        ));
+    }
+
+    #[test]
+    fn duel_closes_on_last_known_prices_once_stale() {
+        let (env, mut manager, mut engine, _) = setup();
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(5_000_000_000u64));
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+
+        // No price updates arrive before the duel ends
+        env.advance_block_time(engine.get_max_price_age() + 60 * 1000);
+        env.set_caller(env.get_account(0));
+        manager.close_duel(duel_id);
+        assert!(matches!(manager.get_duel(duel_id).unwrap().status, DuelStatus::WithdrawalPending));
+    }
+
+    #[test]
+    fn close_ranks_on_oracle_twap_up_to_duel_end() {
+        let (env, mut manager, mut engine, _) = setup();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        oracle.set_min_update_interval(5_000);
+        oracle.update_price("NFT1".to_string(), U512::from(5_000_000_000u64), "market".to_string());
+        engine.set_price_oracle(oracle.address());
+        engine.set_twap_window(60 * 1000);
+        let duel_id = start_two_player_duel(&env, &mut manager);
+        let end_time = manager.get_duel(duel_id).unwrap().end_time;
+
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+
+        // The price sags for most of the duel and only spikes in the final seconds
+        env.set_caller(env.get_account(0));
+        env.advance_block_time(10_000);
+        oracle.update_price("NFT1".to_string(), U512::from(2_000_000_000u64), "market".to_string());
+        env.advance_block_time(45_000);
+        oracle.update_price("NFT1".to_string(), U512::from(8_000_000_000u64), "market".to_string());
+
+        // Nobody can trade once the duel has ended
+        env.advance_block_time(5_000);
+        env.set_caller(env.get_account(2));
+        assert_eq!(
+            engine.try_execute_sell(duel_id, "NFT1".to_string()),
+            Err(TradingError::DuelEnded.into())
+        );
+
+        // Spot ranks player 2 at 13 CSPR, the TWAP up to the end at 5 + 3 = 8 CSPR
+        env.advance_block_time(60 * 1000);
+        env.set_caller(env.get_account(0));
+        oracle.update_price("NFT1".to_string(), U512::from(20_000_000_000u64), "market".to_string());
+        let leaderboard = engine.get_leaderboard(duel_id, vec![env.get_account(2)], end_time);
+        assert_eq!(leaderboard[0].current_value, U512::from(8_000_000_000u64));
+        manager.close_duel(duel_id);
+        assert_eq!(manager.get_duel(duel_id).unwrap().winner, Some(env.get_account(1)));
+    }
+
+    // Both players end flat: player 1 makes four early trades, player 2 two late ones
+    fn close_tied_duel(policy: TieBreakPolicy) -> (HostEnv, FlipDuelManagerHostRef, u64) {
+        let (env, mut manager, mut engine, _) = setup();
+        manager.set_tie_break_policy(policy);
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_000_000_000u64));
+
+        env.advance_block_time(1_000);
+        env.set_caller(env.get_account(1));
+        for _ in 0..2 {
+            engine.execute_buy(duel_id, "NFT1".to_string());
+            engine.execute_sell(duel_id, "NFT1".to_string());
+        }
+
+        env.advance_block_time(10_000);
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+        engine.execute_sell(duel_id, "NFT1".to_string());
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+        (env, manager, duel_id)
+    }
+
+    #[test]
+    fn close_ties_only_on_equal_values() {
+        let (env, mut manager, mut engine, _) = setup();
+        manager.set_tie_break_policy(TieBreakPolicy::SplitPot);
+        let duel_id = start_two_player_duel(&env, &mut manager);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_000_000_000u64));
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+
+        // Player 2 ends half a percent up, the same whole-number gain as flat player 1
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_050_000_000u64));
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winners, vec![env.get_account(2)]);
+        assert!(env.emitted_event(
+            &manager,
+            DuelClosed {
+                duel_id,
+                winner: env.get_account(2),
+                winners: vec![env.get_account(2)],
+                gain_percentage: 0,
+                prize_pool: U512::from(2 * ENTRY_FEE),
+                tie_break: TieBreakPolicy::SplitPot,
+            }
+        ));
+    }
+
+    #[test]
+    fn tie_break_join_order() {
+        let (env, manager, duel_id) = close_tied_duel(TieBreakPolicy::JoinOrder);
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.tie_break, TieBreakPolicy::JoinOrder);
+        assert_eq!(duel.winners, vec![env.get_account(1)]);
+    }
+
+    #[test]
+    fn tie_break_fewest_trades() {
+        let (env, manager, duel_id) = close_tied_duel(TieBreakPolicy::FewestTrades);
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winners, vec![env.get_account(2)]);
+    }
+
+    #[test]
+    fn tie_break_earliest_final_value() {
+        let (env, manager, duel_id) = close_tied_duel(TieBreakPolicy::EarliestFinalValue);
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winners, vec![env.get_account(1)]);
+    }
+
+    #[test]
+    fn tie_break_split_pot() {
+        let (env, mut manager, duel_id) = close_tied_duel(TieBreakPolicy::SplitPot);
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winners, vec![env.get_account(1), env.get_account(2)]);
+        assert_eq!(duel.winner, Some(env.get_account(1)));
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+
+        // 20 CSPR pool minus 5% platform fee, split two ways
+        let share = U512::from(9_500_000_000u64);
+        for player in [env.get_account(2), env.get_account(1)] {
+            let balance = env.balance_of(&player);
+            env.set_caller(player);
+            manager.claim_rewards(duel_id);
+            assert_eq!(env.balance_of(&player), balance + share);
+        }
+        assert!(matches!(manager.get_duel(duel_id).unwrap().status, DuelStatus::Completed));
+    }
+
+    #[test]
+    fn duel_closes_and_pays_out_while_staking_is_paused() {
+        let (env, mut manager, _, mut staking) = setup();
+        let duel_id = start_two_player_duel(&env, &mut manager);
+        env.set_caller(env.get_account(0));
+        staking.pause();
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        env.set_caller(env.get_account(1));
+        manager.claim_rewards(duel_id);
+        assert!(manager.has_claimed_rewards(duel_id, env.get_account(1)));
+    }
+
+    #[test]
+    fn user_duels_are_indexed_on_create_and_join() {
+        let (env, mut manager, _, _) = setup();
+        let (player_1, player_2) = (env.get_account(1), env.get_account(2));
+        let started = start_two_player_duel(&env, &mut manager);
+        env.set_caller(player_1);
+        let open = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+
+        assert_eq!(manager.get_user_duel_count(player_1), 2);
+        assert_eq!(manager.get_user_duels(player_1, 0, 10, None), vec![started, open]);
+        assert_eq!(manager.get_user_duels(player_1, 1, 10, None), vec![open]);
+        assert_eq!(manager.get_user_duels(player_1, 0, 10, Some(DuelStatus::Open)), vec![open]);
+        assert_eq!(manager.get_user_duels(player_1, 0, 1, Some(DuelStatus::Open)), vec![open]);
+        assert!(manager.get_user_duels(player_1, 1, 10, Some(DuelStatus::Open)).is_empty());
+        assert_eq!(manager.get_user_duels(player_2, 0, 10, Some(DuelStatus::Active)), vec![started]);
+        assert!(manager.get_user_duels(env.get_account(3), 0, 10, None).is_empty());
+    }
+
+    #[test]
+    fn finished_duels_leave_active_list() {
+        let (env, mut manager, _, _) = setup();
+        let first = start_two_player_duel(&env, &mut manager);
+        env.set_caller(env.get_account(1));
+        let cancelled = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        let open = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        assert_eq!(manager.get_active_duels(0, 10), vec![first, cancelled, open]);
+
+        manager.cancel_duel(cancelled);
+        assert_eq!(manager.get_active_duels(0, 10), vec![first, open]);
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(first);
+        assert_eq!(manager.get_active_duels(0, 10), vec![open]);
+        assert_eq!(manager.get_active_duel_count(), 1);
+        assert_eq!(manager.get_active_duels(1, 10), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn compact_active_duels_is_owner_only() {
+        let (env, mut manager, _, _) = setup();
+        start_two_player_duel(&env, &mut manager);
+        assert_eq!(manager.try_compact_active_duels(0, 10), Err(Error::Unauthorized.into()));
+
+        env.set_caller(env.get_account(0));
+        assert_eq!(manager.compact_active_duels(0, 10), 0);
+        assert_eq!(manager.get_active_duel_count(), 1);
+    }
+
+    #[test]
+    fn cancelled_duel_refunds_are_pulled_per_player() {
+        let (env, mut manager, _, _) = setup();
+        let creator = env.get_account(1);
+        env.set_caller(creator);
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        manager.cancel_duel(duel_id);
+        assert_eq!(manager.try_claim_refund(duel_id), Err(Error::WithdrawalNotReady.into()));
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+
+        env.set_caller(env.get_account(2));
+        assert_eq!(manager.try_claim_refund(duel_id), Err(Error::NotParticipant.into()));
+
+        let balance = env.balance_of(&creator);
+        env.set_caller(creator);
+        manager.claim_refund(duel_id);
+        assert_eq!(env.balance_of(&creator), balance + U512::from(ENTRY_FEE));
+        assert!(manager.has_claimed_rewards(duel_id, creator));
+        assert!(env.emitted_event(
+            &manager,
+            RefundClaimed { duel_id, player: creator, amount: U512::from(ENTRY_FEE) }
+        ));
+        assert_eq!(manager.try_claim_refund(duel_id), Err(Error::AlreadyClaimed.into()));
+    }
+
+    #[test]
+    fn participant_leaves_open_duel_and_claims_refund() {
+        let (env, mut manager, _, _) = setup();
+        let (creator, leaver) = (env.get_account(1), env.get_account(2));
+        env.set_caller(creator);
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), Vec::new(), None);
+        assert_eq!(manager.try_leave_duel(duel_id), Err(Error::CreatorCannotLeave.into()));
+
+        env.set_caller(leaver);
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        manager.leave_duel(duel_id);
+
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.participants, vec![creator]);
+        assert_eq!(duel.prize_pool, U512::from(ENTRY_FEE));
+        assert_eq!(duel.stcspr_staked, U256::from(ENTRY_FEE));
+        assert_eq!(manager.try_leave_duel(duel_id), Err(Error::NotParticipant.into()));
+        assert_eq!(
+            manager.with_tokens(U512::from(ENTRY_FEE)).try_join_duel(duel_id),
+            Err(Error::AlreadyLeft.into())
+        );
+        assert_eq!(manager.try_claim_leave_refund(duel_id), Err(Error::WithdrawalNotReady.into()));
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+
+        let balance = env.balance_of(&leaver);
+        env.set_caller(leaver);
+        manager.claim_leave_refund(duel_id);
+        assert_eq!(env.balance_of(&leaver), balance + U512::from(ENTRY_FEE));
+        assert_eq!(manager.try_claim_leave_refund(duel_id), Err(Error::AlreadyClaimed.into()));
+    }
+
+    #[test]
+    fn leaver_unstakes_exactly_their_own_stake() {
+        let (env, mut manager, _, staking) = setup();
+        let (creator, leaver) = (env.get_account(1), env.get_account(2));
+        env.set_caller(creator);
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), Vec::new(), None);
+
+        // Rewards raise the exchange rate, so the leaver is minted less stCSPR than the creator
+        env.set_caller(env.get_account(0));
+        staking.with_tokens(U512::from(ENTRY_FEE / 10)).harvest_rewards();
+        env.set_caller(leaver);
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        let leaver_stake = manager.get_duel(duel_id).unwrap().stakes[1];
+        assert_eq!(leaver_stake.0, leaver);
+        assert!(leaver_stake.1 < U256::from(ENTRY_FEE));
+
+        manager.leave_duel(duel_id);
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.stakes, vec![(creator, U256::from(ENTRY_FEE))]);
+        assert_eq!(duel.stcspr_staked, U256::from(ENTRY_FEE));
+    }
+
+    #[test]
+    fn expired_open_duel_can_be_cancelled_by_anyone() {
+        let (env, mut manager, _, _) = setup();
+        let (creator, joiner) = (env.get_account(1), env.get_account(2));
+        env.set_caller(creator);
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), Vec::new(), Some(120));
+        env.set_caller(joiner);
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+
+        env.set_caller(env.get_account(4));
+        assert_eq!(manager.try_cancel_expired_duel(duel_id), Err(Error::DuelNotExpired.into()));
+
+        env.advance_block_time(120 * 1000);
+        env.set_caller(env.get_account(3));
+        assert_eq!(manager.with_tokens(U512::from(ENTRY_FEE)).try_join_duel(duel_id), Err(Error::JoinDeadlinePassed.into()));
+
+        env.set_caller(env.get_account(4));
+        manager.cancel_expired_duel(duel_id);
+        assert!(matches!(manager.get_duel(duel_id).unwrap().status, DuelStatus::Cancelled));
+        assert!(manager.get_active_duels(0, 10).is_empty());
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        for player in [creator, joiner] {
+            let balance = env.balance_of(&player);
+            env.set_caller(player);
+            manager.claim_refund(duel_id);
+            assert_eq!(env.balance_of(&player), balance + U512::from(ENTRY_FEE));
+        }
+    }
+
+    #[test]
+    fn duel_without_join_window_never_expires() {
+        let (env, mut manager, _, _) = setup();
+        env.set_caller(env.get_account(1));
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        assert_eq!(
+            manager.with_tokens(U512::from(ENTRY_FEE)).try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), Some(0)),
+            Err(Error::InvalidJoinWindow.into())
+        );
+        for window in [7 * 24 * 60 * 60 + 1, u64::MAX] {
+            assert_eq!(
+                manager.with_tokens(U512::from(ENTRY_FEE)).try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), Some(window)),
+                Err(Error::InvalidJoinWindow.into())
+            );
+        }
+
+        env.advance_block_time(1_000_000_000);
+        assert_eq!(manager.try_cancel_expired_duel(duel_id), Err(Error::DuelNotExpired.into()));
+    }
+
+    #[test]
+    fn refund_dust_goes_to_creator() {
+        let env = odra_test::env();
+        let players = [env.get_account(1), env.get_account(2), env.get_account(3)];
+        let refunds = allocate_refunds(&players, U512::from(100u64));
+        assert_eq!(
+            refunds,
+            vec![(players[0], U512::from(34u64)), (players[1], U512::from(33u64)), (players[2], U512::from(33u64))]
+        );
+    }
+
+    #[test]
+    fn entry_fees_move_into_liquid_stake() {
+        let (env, manager, _, staking) = setup();
+        let (creator, joiner) = (env.get_account(1), env.get_account(2));
+        let creator_balance = env.balance_of(&creator);
+        let joiner_balance = env.balance_of(&joiner);
+
+        env.set_caller(creator);
+        let duel_id = manager
+            .with_tokens(U512::from(ENTRY_FEE))
+            .create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), Vec::new(), None);
+        env.set_caller(joiner);
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+
+        assert_eq!(env.balance_of(&creator), creator_balance - U512::from(ENTRY_FEE));
+        assert_eq!(env.balance_of(&joiner), joiner_balance - U512::from(ENTRY_FEE));
+        assert_eq!(env.balance_of(&staking), U512::from(2 * ENTRY_FEE));
+        assert_eq!(env.balance_of(&manager), U512::zero());
+        assert_eq!(staking.get_stcspr_balance(manager.address()), U256::from(2 * ENTRY_FEE));
+    }
+
+    #[test]
+    fn incorrect_fee_is_rejected() {
+        let (env, mut manager, _, _) = setup();
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            manager
+                .with_tokens(U512::from(ENTRY_FEE - 1))
+                .try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None),
+            Err(Error::IncorrectFee.into())
+        );
+        assert_eq!(
+            manager.try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None),
+            Err(Error::IncorrectFee.into())
+        );
+
+        let duel_id = manager
+            .with_tokens(U512::from(ENTRY_FEE))
+            .create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None);
+        env.set_caller(env.get_account(2));
+        assert_eq!(
+            manager.with_tokens(U512::from(2 * ENTRY_FEE)).try_join_duel(duel_id),
+            Err(Error::IncorrectFee.into())
+        );
+    }
+
+    #[test]
+    fn duel_trades_are_limited_to_its_collection() {
+        let (env, mut manager, mut engine, _) = setup();
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            manager
+                .with_tokens(U512::from(ENTRY_FEE))
+                .try_create_duel(60, "unlisted".to_string(), 2, U512::from(ENTRY_FEE), Vec::new(), None),
+            Err(Error::UnknownCollection.into())
+        );
+
+        let duel_id = start_two_player_duel(&env, &mut manager);
+        assert_eq!(engine.get_duel_nfts(duel_id), vec!["NFT1".to_string(), "NFT2".to_string()]);
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            engine.try_execute_buy(duel_id, "NFT1".to_string()),
+            Err(TradingError::PriceUnavailable.into())
+        );
+        assert_eq!(
+            engine.try_execute_buy(duel_id, "OTHER".to_string()),
+            Err(TradingError::NftNotTradable.into())
+        );
+    }
+
+    #[test]
+    fn create_rejects_invalid_payout_table() {
+        let (env, manager, _, _) = setup();
+        env.set_caller(env.get_account(1));
+        for table in [vec![50, 40], vec![30, 70], vec![100, 0], vec![50, 30, 20]] {
+            assert_eq!(
+                manager.with_tokens(U512::from(ENTRY_FEE)).try_create_duel(60, "collection".to_string(), 2, U512::from(ENTRY_FEE), table, None),
+                Err(Error::InvalidPayoutTable.into())
+            );
+        }
+    }
+
+    #[test]
+    fn payout_table_rescales_to_ranked_players() {
+        let (env, mut manager, mut engine, _) = setup();
+        env.set_caller(env.get_account(1));
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), vec![60, 30, 10], None);
+        env.set_caller(env.get_account(2));
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        env.set_caller(env.get_account(1));
+        manager.start_duel(duel_id);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(5_000_000_000u64));
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(10_000_000_000u64));
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+
+        // 19 CSPR prize split 60:30 between the two ranked players, dust to the winner
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(
+            duel.allocations,
+            vec![
+                (env.get_account(2), U512::from(12_666_666_667u64)),
+                (env.get_account(1), U512::from(6_333_333_333u64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranked_players_claim_payout_shares() {
+        let (env, mut manager, mut engine, _) = setup();
+        env.set_caller(env.get_account(1));
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), vec![60, 30, 10], None);
+        env.set_caller(env.get_account(2));
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        env.set_caller(env.get_account(3));
+        manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+
+        // Player 3 doubles the NFT value, player 2 loses on it, player 1 stays flat
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(5_000_000_000u64));
+        engine.update_nft_price("NFT2".to_string(), U512::from(5_000_000_000u64));
+        env.set_caller(env.get_account(3));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+        env.set_caller(env.get_account(2));
+        engine.execute_buy(duel_id, "NFT2".to_string());
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(10_000_000_000u64));
+        engine.update_nft_price("NFT2".to_string(), U512::from(1_000_000_000u64));
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+
+        // 30 CSPR pool minus 5% platform fee = 28.5 CSPR
+        let expected = [
+            (env.get_account(3), 17_100_000_000u64),
+            (env.get_account(1), 8_550_000_000u64),
+            (env.get_account(2), 2_850_000_000u64),
+        ];
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(
+            duel.allocations,
+            expected.iter().map(|(player, amount)| (*player, U512::from(*amount))).collect::<Vec<_>>()
+        );
+
+        for (player, amount) in expected {
+            let balance = env.balance_of(&player);
+            env.set_caller(player);
+            manager.claim_rewards(duel_id);
+            assert_eq!(env.balance_of(&player), balance + U512::from(amount));
+            assert!(manager.has_claimed_rewards(duel_id, player));
+            assert_eq!(manager.try_claim_rewards(duel_id), Err(Error::AlreadyClaimed.into()));
+        }
+        assert!(matches!(manager.get_duel(duel_id).unwrap().status, DuelStatus::Completed));
+    }
+
+    #[test]
+    fn players_tied_below_first_pool_their_slots() {
+        let (env, mut manager, mut engine, _) = setup();
+        env.set_caller(env.get_account(1));
+        let duel_id = manager.with_tokens(U512::from(ENTRY_FEE)).create_duel(60, "collection".to_string(), 3, U512::from(ENTRY_FEE), vec![60, 30, 10], None);
+        for player in [env.get_account(2), env.get_account(3)] {
+            env.set_caller(player);
+            manager.with_tokens(U512::from(ENTRY_FEE)).join_duel(duel_id);
+        }
+
+        // Player 3 doubles the NFT value, players 1 and 2 stay flat
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(5_000_000_000u64));
+        env.set_caller(env.get_account(3));
+        engine.execute_buy(duel_id, "NFT1".to_string());
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(10_000_000_000u64));
+
+        env.advance_block_time(60 * 1000);
+        manager.close_duel(duel_id);
+
+        // 28.5 CSPR after fees: 60% to player 3, the 30% and 10% slots shared
+        let duel = manager.get_duel(duel_id).unwrap();
+        assert_eq!(duel.winners, vec![env.get_account(3)]);
+        assert_eq!(
+            duel.allocations,
+            vec![
+                (env.get_account(3), U512::from(17_100_000_000u64)),
+                (env.get_account(1), U512::from(5_700_000_000u64)),
+                (env.get_account(2), U512::from(5_700_000_000u64)),
+            ]
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 use odra::prelude::*;
 use odra::casper_types::{U512, U256, PublicKey};
+use odra_modules::cep18_token::Cep18;
 
 // ============================================================================
 // ERRORS
@@ -31,6 +32,9 @@ pub enum Error {
     RewardsTooHigh = 20,
     ValueOverflow = 21,
     Unauthorized = 22,
+    ValidatorNotFound = 23,
+    InvalidUnbondingPeriod = 24,
+    InvalidFee = 25,
 }
 
 // ============================================================================
@@ -53,6 +57,14 @@ pub struct UnstakeRequested {
     pub cspr_amount: U512,
 }
 
+#[odra::event]
+pub struct InstantUnstaked {
+    pub staker: Address,
+    pub stcspr_amount: U512,
+    pub cspr_amount: U512,
+    pub fee: U512,
+}
+
 #[odra::event]
 pub struct Claimed {
     pub staker: Address,
@@ -66,11 +78,30 @@ pub struct RewardsHarvested {
     pub new_exchange_rate: U512,
 }
 
+#[odra::event]
+pub struct ProtocolFeeCollected {
+    pub treasury: Address,
+    pub cspr_amount: U512,
+    pub stcspr_minted: U256,
+}
+
 #[odra::event]
 pub struct ValidatorAdded {
     pub validator: PublicKey,
 }
 
+#[odra::event]
+pub struct ValidatorDeactivated {
+    pub validator: PublicKey,
+    pub undelegated: U512,
+}
+
+#[odra::event]
+pub struct ValidatorRemoved {
+    pub validator: PublicKey,
+    pub undelegated: U512,
+}
+
 #[odra::event]
 pub struct AdminDelegated {
     pub validator: PublicKey,
@@ -83,11 +114,63 @@ pub struct AdminUndelegated {
     pub amount: U512,
 }
 
+#[odra::event]
+pub struct UndelegationCompleted {
+    pub request_id: u64,
+    pub cspr_amount: U512,
+}
+
+#[odra::event]
+pub struct UnbondingPeriodUpdated {
+    pub unbonding_period_ms: u64,
+}
+
+#[odra::event]
+pub struct InstantUnstakeFeeUpdated {
+    pub fee_bps: u32,
+}
+
+#[odra::event]
+pub struct ProtocolFeeUpdated {
+    pub fee_bps: u32,
+}
+
+#[odra::event]
+pub struct TreasuryUpdated {
+    pub treasury: Address,
+}
+
+#[odra::event]
+pub struct DuelManagerUpdated {
+    pub duel_manager: Address,
+}
+
+#[odra::event]
+pub struct GuardianUpdated {
+    pub guardian: Address,
+}
+
+#[odra::event]
+pub struct Paused {
+    pub by: Address,
+}
+
+#[odra::event]
+pub struct Unpaused {
+    pub by: Address,
+}
+
 #[odra::event]
 pub struct LiquidityAdded {
     pub amount: U512,
 }
 
+#[odra::event]
+pub struct AutoDelegated {
+    pub validator: PublicKey,
+    pub amount: U512,
+}
+
 // ============================================================================
 // WITHDRAWAL REQUEST
 // ============================================================================
@@ -96,7 +179,8 @@ pub struct LiquidityAdded {
 pub struct WithdrawalRequest {
     pub staker: Address,
     pub cspr_amount: U512,
-    pub request_block: u64,
+    pub requested_at: u64,
+    pub expected_ready_at: u64,
     pub claimed: bool,
 }
 
@@ -105,14 +189,16 @@ impl odra::casper_types::bytesrepr::ToBytes for WithdrawalRequest {
         let mut result = Vec::new();
         result.append(&mut self.staker.to_bytes()?);
         result.append(&mut self.cspr_amount.to_bytes()?);
-        result.append(&mut self.request_block.to_bytes()?);
+        result.append(&mut self.requested_at.to_bytes()?);
+        result.append(&mut self.expected_ready_at.to_bytes()?);
         result.append(&mut self.claimed.to_bytes()?);
         Ok(result)
     }
     fn serialized_length(&self) -> usize {
         self.staker.serialized_length()
             + self.cspr_amount.serialized_length()
-            + self.request_block.serialized_length()
+            + self.requested_at.serialized_length()
+            + self.expected_ready_at.serialized_length()
             + self.claimed.serialized_length()
     }
 }
@@ -121,13 +207,15 @@ impl odra::casper_types::bytesrepr::FromBytes for WithdrawalRequest {
     fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), odra::casper_types::bytesrepr::Error> {
         let (staker, remainder) = Address::from_bytes(bytes)?;
         let (cspr_amount, remainder) = U512::from_bytes(remainder)?;
-        let (request_block, remainder) = u64::from_bytes(remainder)?;
+        let (requested_at, remainder) = u64::from_bytes(remainder)?;
+        let (expected_ready_at, remainder) = u64::from_bytes(remainder)?;
         let (claimed, remainder) = bool::from_bytes(remainder)?;
         Ok((
             WithdrawalRequest {
                 staker,
                 cspr_amount,
-                request_block,
+                requested_at,
+                expected_ready_at,
                 claimed,
             },
             remainder,
@@ -141,21 +229,80 @@ impl odra::casper_types::CLTyped for WithdrawalRequest {
     }
 }
 
+/// Read-side view of a withdrawal request
+#[odra::odra_type]
+pub struct WithdrawalInfo {
+    pub request_id: u64,
+    pub cspr_amount: U512,
+    pub ready_at: u64,
+    pub claimed: bool,
+}
+
+// ============================================================================
+// SETTINGS
+// ============================================================================
+
+#[odra::odra_type]
+pub enum AllocationMode {
+    EqualWeight,  // Every active validator targets the same delegation
+    TargetWeight, // Validators target their configured weight share
+}
+
+/// Owner-tunable settings, each in its own storage slot, fees are in basis points.
+/// Kept in a submodule since an Odra module holds at most 15 fields.
+#[odra::module]
+pub struct StakeSettings {
+    // Delegation strategy
+    allocation_mode: Var<AllocationMode>,
+    target_weights: Mapping<PublicKey, u32>,
+    delegate_on_stake: Var<bool>,
+    duel_manager: Var<Address>,
+    // Withdrawals and fees
+    unbonding_period: Var<u64>,
+    instant_unstake_fee_bps: Var<u32>,
+    protocol_fee_bps: Var<u32>,
+    treasury: Var<Address>,
+    // Emergency stop
+    paused: Var<bool>,
+    guardian: Var<Address>,
+    claims_while_paused: Var<bool>,
+}
+
+// ============================================================================
+// VALIDATORS
+// ============================================================================
+
+#[odra::odra_type]
+pub struct ValidatorInfo {
+    pub validator: PublicKey,
+    pub delegated: U512,
+    pub active: bool,
+}
+
 // ============================================================================
 // LIQUIDSTAKE CONTRACT - Pool-Based Liquid Staking
 // ============================================================================
 
+const TOKEN_NAME: &str = "Staked CSPR";
+const TOKEN_SYMBOL: &str = "stCSPR";
+const TOKEN_DECIMALS: u8 = 9;
 const RATE_PRECISION: u64 = 1_000_000_000;
 const MIN_DELEGATION: u64 = 500_000_000_000;
-const UNBONDING_BLOCKS: u64 = 5000;
+// Block time is in milliseconds; the default covers 7 eras of ~2 hours each
+pub const DEFAULT_UNBONDING_PERIOD_MS: u64 = 14 * 60 * 60 * 1000;
+const MIN_UNBONDING_PERIOD_MS: u64 = 60 * 60 * 1000;
+const MAX_UNBONDING_PERIOD_MS: u64 = 30 * 24 * 60 * 60 * 1000;
 const MAX_VALIDATORS: usize = 20;
+const BPS_DENOMINATOR: u32 = 10_000;
+const DEFAULT_INSTANT_UNSTAKE_FEE_BPS: u32 = 50;
+const MAX_INSTANT_UNSTAKE_FEE_BPS: u32 = 1_000;
+const MAX_PROTOCOL_FEE_BPS: u32 = 2_000;
 
-#[odra::module(events = [Staked, UnstakeRequested, Claimed, RewardsHarvested, ValidatorAdded, AdminDelegated, AdminUndelegated, LiquidityAdded], errors = Error)]
+#[odra::module(events = [Staked, UnstakeRequested, InstantUnstaked, Claimed, RewardsHarvested, ProtocolFeeCollected, ValidatorAdded, ValidatorDeactivated, ValidatorRemoved, AdminDelegated, AdminUndelegated, UndelegationCompleted, UnbondingPeriodUpdated, InstantUnstakeFeeUpdated, ProtocolFeeUpdated, TreasuryUpdated, DuelManagerUpdated, GuardianUpdated, Paused, Unpaused, LiquidityAdded, AutoDelegated], errors = Error)]
 pub struct LiquidStake {
     owner: Var<Address>,
-    // Manual token tracking instead of CEP-18 SubModule
-    balances: Mapping<Address, U256>,
-    total_supply: Var<U256>,
+    // stCSPR is a CEP-18 token
+    token: SubModule<Cep18>,
     total_cspr_pool: Var<U512>,
     available_liquidity: Var<U512>,
     pending_withdrawals: Var<U512>,
@@ -168,51 +315,103 @@ pub struct LiquidStake {
     next_request_id: Var<u64>,
     user_requests: Mapping<(Address, u64), u64>,
     user_request_count: Mapping<Address, u64>,
+    settings: SubModule<StakeSettings>,
 }
 
 #[odra::module]
 impl LiquidStake {
+    delegate! {
+        to self.token {
+            fn name(&self) -> String;
+            fn symbol(&self) -> String;
+            fn decimals(&self) -> u8;
+            fn total_supply(&self) -> U256;
+            fn balance_of(&self, address: &Address) -> U256;
+            fn allowance(&self, owner: &Address, spender: &Address) -> U256;
+            fn approve(&mut self, spender: &Address, amount: &U256);
+            fn decrease_allowance(&mut self, spender: &Address, decr_by: &U256);
+            fn increase_allowance(&mut self, spender: &Address, inc_by: &U256);
+            fn transfer(&mut self, recipient: &Address, amount: &U256);
+            fn transfer_from(&mut self, owner: &Address, recipient: &Address, amount: &U256);
+        }
+    }
+
     pub fn init(&mut self, owner: Address) {
         self.owner.set(owner);
-        self.total_supply.set(U256::zero());
+        self.token.init(TOKEN_SYMBOL.to_string(), TOKEN_NAME.to_string(), TOKEN_DECIMALS, U256::zero());
         self.total_cspr_pool.set(U512::zero());
         self.available_liquidity.set(U512::zero());
         self.pending_withdrawals.set(U512::zero());
         self.pending_undelegations.set(U512::zero());
         self.validator_count.set(0);
+        self.settings.allocation_mode.set(AllocationMode::EqualWeight);
+        self.settings.unbonding_period.set(DEFAULT_UNBONDING_PERIOD_MS);
+        self.settings.instant_unstake_fee_bps.set(DEFAULT_INSTANT_UNSTAKE_FEE_BPS);
+        self.settings.treasury.set(owner);
         self.next_request_id.set(1);
     }
 
-    /// Stake CSPR - ultra-simplified for cross-contract calls
-    /// Simple 1:1 CSPR to stCSPR minting, no validations
-    pub fn stake(&mut self, _validator: PublicKey, cspr_amount: U512) -> U256 {
+    /// Stake CSPR through an approved validator
+    /// Attached CSPR must match the amount, stCSPR is minted at the pool exchange rate
+    /// MIN_DELEGATION applies when the pooled CSPR is delegated, not per deposit
+    #[odra(payable)]
+    pub fn stake(&mut self, validator: PublicKey, cspr_amount: U512) -> U256 {
+        self.require_not_paused();
         let staker = self.env().caller();
 
-        // Simple 1:1 conversion
-        let stcspr_to_mint = u512_to_u256(cspr_amount);
+        if cspr_amount == U512::zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+        if self.env().attached_value() != cspr_amount {
+            self.env().revert(Error::InsufficientBalance);
+        }
+        if !self.validator_active.get(&validator).unwrap_or(false) {
+            self.env().revert(Error::ValidatorNotApproved);
+        }
 
-        // Mint stCSPR
-        let balance = self.balances.get(&staker).unwrap_or(U256::zero());
-        self.balances.set(&staker, balance + stcspr_to_mint);
+        // Price the deposit before it is added to the pool
+        let stcspr_to_mint = self.cspr_to_stcspr(cspr_amount);
+        if stcspr_to_mint == U256::zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
 
-        let supply = self.total_supply.get_or_default();
-        self.total_supply.set(supply + stcspr_to_mint);
+        // Mint stCSPR
+        self.token.raw_mint(&staker, &stcspr_to_mint);
 
         let pool = self.total_cspr_pool.get_or_default();
         self.total_cspr_pool.set(pool + cspr_amount);
 
+        // Staked CSPR is held as liquidity until delegated
+        let liquidity = self.available_liquidity.get_or_default();
+        self.available_liquidity.set(liquidity + cspr_amount);
+
+        self.env().emit_event(Staked {
+            staker,
+            validator,
+            cspr_amount,
+            stcspr_minted: stcspr_to_mint,
+        });
+
+        if self.settings.delegate_on_stake.get_or_default() {
+            self.allocate_liquidity();
+        }
+
         stcspr_to_mint
     }
 
+    /// The duel manager may still unstake while paused so running duels can close, cancel and refund
     pub fn request_unstake(&mut self, stcspr_amount: U512) -> u64 {
         let staker = self.env().caller();
+        if self.settings.duel_manager.get() != Some(staker) {
+            self.require_not_paused();
+        }
         let stcspr_amount_u256 = u512_to_u256(stcspr_amount);
 
         if stcspr_amount_u256 == U256::zero() {
             self.env().revert(Error::ZeroAmount);
         }
 
-        let staker_balance = self.balances.get(&staker).unwrap_or(U256::zero());
+        let staker_balance = self.token.balance_of(&staker);
         if stcspr_amount_u256 > staker_balance {
             self.env().revert(Error::InsufficientStCsprBalance);
         }
@@ -223,10 +422,8 @@ impl LiquidStake {
             self.env().revert(Error::InsufficientPoolBalance);
         }
 
-        // Burn stCSPR manually
-        self.balances.set(&staker, staker_balance - stcspr_amount_u256);
-        let supply = self.total_supply.get_or_default();
-        self.total_supply.set(supply - stcspr_amount_u256);
+        // Burn stCSPR
+        self.token.raw_burn(&staker, &stcspr_amount_u256);
 
         self.total_cspr_pool.set(pool - cspr_to_return);
 
@@ -236,33 +433,74 @@ impl LiquidStake {
         let pending_undel = self.pending_undelegations.get_or_default();
         self.pending_undelegations.set(pending_undel + cspr_to_return);
 
-        let request_id = self.next_request_id.get_or_default();
-        self.next_request_id.set(request_id + 1);
+        let request_id = self.record_withdrawal(staker, cspr_to_return);
 
-        let request = WithdrawalRequest {
+        self.env().emit_event(UnstakeRequested {
             staker,
+            request_id,
+            stcspr_amount,
             cspr_amount: cspr_to_return,
-            request_block: self.env().get_block_time(),
-            claimed: false,
-        };
-        self.withdrawal_requests.set(&request_id, request);
+        });
 
-        let user_count = self.user_request_count.get(&staker).unwrap_or(0);
-        self.user_requests.set(&(staker, user_count), request_id);
-        self.user_request_count.set(&staker, user_count + 1);
+        request_id
+    }
 
-        self.env().emit_event(UnstakeRequested {
+    /// Redeem stCSPR straight from the liquidity buffer, skipping unbonding
+    /// The fee stays in the pool and lifts the exchange rate for remaining stakers
+    pub fn instant_unstake(&mut self, stcspr_amount: U512) -> U512 {
+        self.require_not_paused();
+        let staker = self.env().caller();
+        let stcspr_amount_u256 = u512_to_u256(stcspr_amount);
+
+        if stcspr_amount_u256 == U256::zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+
+        let staker_balance = self.token.balance_of(&staker);
+        if stcspr_amount_u256 > staker_balance {
+            self.env().revert(Error::InsufficientStCsprBalance);
+        }
+
+        let cspr_value = self.stcspr_to_cspr(stcspr_amount_u256);
+        let fee_bps = self.get_instant_unstake_fee();
+        let fee = cspr_value * U512::from(fee_bps) / U512::from(BPS_DENOMINATOR);
+        let cspr_to_return = cspr_value - fee;
+        if cspr_to_return == U512::zero() {
+            self.env().revert(Error::ZeroAmount);
+        }
+
+        // Liquidity reserved for queued withdrawals and duel stakes is not part of the buffer
+        let liquidity = self.available_liquidity.get_or_default();
+        let buffer = liquidity.saturating_sub(self.reserved_liquidity());
+        if cspr_to_return > buffer {
+            self.env().revert(Error::InsufficientLiquidity);
+        }
+
+        self.token.raw_burn(&staker, &stcspr_amount_u256);
+
+        let pool = self.total_cspr_pool.get_or_default();
+        self.total_cspr_pool.set(pool - cspr_to_return);
+        self.available_liquidity.set(liquidity - cspr_to_return);
+
+        self.env().transfer_tokens(&staker, &cspr_to_return);
+
+        self.env().emit_event(InstantUnstaked {
             staker,
-            request_id,
             stcspr_amount,
             cspr_amount: cspr_to_return,
+            fee,
         });
 
-        request_id
+        cspr_to_return
     }
 
     pub fn claim(&mut self, request_id: u64) {
         let caller = self.env().caller();
+        let exempt = self.settings.claims_while_paused.get_or_default()
+            || self.settings.duel_manager.get() == Some(caller);
+        if self.is_paused() && !exempt {
+            self.env().revert(Error::ContractPaused);
+        }
         let request = self.withdrawal_requests.get(&request_id);
         if request.is_none() {
             self.env().revert(Error::WithdrawalNotFound);
@@ -277,8 +515,7 @@ impl LiquidStake {
             self.env().revert(Error::WithdrawalAlreadyClaimed);
         }
 
-        let current_block = self.env().get_block_time();
-        if current_block < request.request_block + UNBONDING_BLOCKS {
+        if self.env().get_block_time() < request.expected_ready_at {
             self.env().revert(Error::WithdrawalNotReady);
         }
 
@@ -306,7 +543,7 @@ impl LiquidStake {
 
     pub fn get_exchange_rate(&self) -> U512 {
         let total_cspr = self.total_cspr_pool.get_or_default();
-        let total_stcspr = self.total_supply.get_or_default();
+        let total_stcspr = self.token.total_supply();
 
         if total_stcspr == U256::zero() {
             return U512::from(RATE_PRECISION);
@@ -320,7 +557,7 @@ impl LiquidStake {
 
     fn cspr_to_stcspr(&self, cspr_amount: U512) -> U256 {
         let total_cspr = self.total_cspr_pool.get_or_default();
-        let total_stcspr = self.total_supply.get_or_default();
+        let total_stcspr = self.token.total_supply();
 
         if total_stcspr == U256::zero() || total_cspr == U512::zero() {
             return u512_to_u256(cspr_amount);
@@ -333,7 +570,7 @@ impl LiquidStake {
 
     fn stcspr_to_cspr(&self, stcspr_amount: U256) -> U512 {
         let total_cspr = self.total_cspr_pool.get_or_default();
-        let total_stcspr = self.total_supply.get_or_default();
+        let total_stcspr = self.token.total_supply();
 
         if total_stcspr == U256::zero() {
             return U512::zero();
@@ -345,12 +582,80 @@ impl LiquidStake {
         (stcspr_512 * total_cspr) / total_stcspr_512
     }
 
+    /// Halt deposits, unstaking and delegation (owner or guardian)
+    pub fn pause(&mut self) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner && self.settings.guardian.get() != Some(caller) {
+            self.env().revert(Error::Unauthorized);
+        }
+        if self.is_paused() {
+            self.env().revert(Error::ContractPaused);
+        }
+
+        self.settings.paused.set(true);
+        self.env().emit_event(Paused { by: caller });
+    }
+
+    pub fn unpause(&mut self) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        if self.is_paused() {
+            self.settings.paused.set(false);
+            self.env().emit_event(Unpaused { by: caller });
+        }
+    }
+
+    pub fn set_guardian(&mut self, guardian: Address) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.settings.guardian.set(guardian);
+        self.env().emit_event(GuardianUpdated { guardian });
+    }
+
+    /// Let users claim matured withdrawals while the contract is paused
+    pub fn set_claims_while_paused(&mut self, allowed: bool) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.settings.claims_while_paused.set(allowed);
+    }
+
+    fn require_not_paused(&self) {
+        if self.is_paused() {
+            self.env().revert(Error::ContractPaused);
+        }
+    }
+
+    // Shares worth `fee` at the post-mint rate: fee * supply / (pool - fee)
+    fn fee_to_stcspr(&self, fee: U512) -> U256 {
+        let total_cspr = self.total_cspr_pool.get_or_default();
+        let total_stcspr = self.token.total_supply();
+
+        if total_stcspr == U256::zero() || total_cspr <= fee {
+            return u512_to_u256(fee);
+        }
+
+        let total_stcspr_512 = u256_to_u512(total_stcspr);
+        u512_to_u256(fee * total_stcspr_512 / (total_cspr - fee))
+    }
+
     pub fn admin_delegate(&mut self, validator: PublicKey, amount: U512) {
         let caller = self.env().caller();
         let owner = self.owner.get().unwrap();
         if caller != owner {
             self.env().revert(Error::Unauthorized);
         }
+        self.require_not_paused();
 
         if amount == U512::zero() {
             self.env().revert(Error::ZeroAmount);
@@ -384,6 +689,90 @@ impl LiquidStake {
         });
     }
 
+    /// Delegate idle liquidity across active validators per the strategy (callable by anyone)
+    pub fn rebalance(&mut self) -> U512 {
+        self.require_not_paused();
+        let delegated = self.allocate_liquidity();
+        if delegated == U512::zero() {
+            self.env().revert(Error::NothingToDelegate);
+        }
+        delegated
+    }
+
+    pub fn set_allocation_mode(&mut self, mode: AllocationMode) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.settings.allocation_mode.set(mode);
+    }
+
+    pub fn set_target_weight(&mut self, validator: PublicKey, weight: u32) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        if !self.validator_active.get(&validator).unwrap_or(false) {
+            self.env().revert(Error::ValidatorNotApproved);
+        }
+        self.settings.target_weights.set(&validator, weight);
+    }
+
+    pub fn set_delegate_on_stake(&mut self, enabled: bool) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.settings.delegate_on_stake.set(enabled);
+    }
+
+    // Liquidity reserved for pending withdrawals and duel stakes is never delegated
+    fn allocate_liquidity(&mut self) -> U512 {
+        let liquidity = self.available_liquidity.get_or_default();
+        let deployable = liquidity.saturating_sub(self.reserved_liquidity());
+        if deployable == U512::zero() {
+            return U512::zero();
+        }
+
+        let mode = self.get_allocation_mode();
+        let validators: Vec<(PublicKey, U512, U512)> = self
+            .all_validators()
+            .into_iter()
+            .filter(|validator| self.validator_active.get(validator).unwrap_or(false))
+            .map(|validator| {
+                let delegated = self.validator_delegated.get(&validator).unwrap_or(U512::zero());
+                let weight = match mode {
+                    AllocationMode::EqualWeight => U512::one(),
+                    AllocationMode::TargetWeight => U512::from(self.get_target_weight(validator.clone())),
+                };
+                (validator, delegated, weight)
+            })
+            .collect();
+
+        let mut total = U512::zero();
+        for (validator, amount) in plan_allocation(&validators, deployable) {
+            let current_delegated = self.validator_delegated.get(&validator).unwrap_or(U512::zero());
+            self.validator_delegated.set(&validator, current_delegated + amount);
+            total += amount;
+
+            #[cfg(not(test))]
+            {
+                self.env().delegate(validator.clone(), amount);
+            }
+
+            self.env().emit_event(AutoDelegated {
+                validator,
+                amount,
+            });
+        }
+
+        self.available_liquidity.set(liquidity - total);
+        total
+    }
+
     pub fn admin_undelegate(&mut self, validator: PublicKey, amount: U512) {
         let caller = self.env().caller();
         let owner = self.owner.get().unwrap();
@@ -400,6 +789,71 @@ impl LiquidStake {
             self.env().revert(Error::UndelegateAmountExceedsDelegation);
         }
 
+        self.undelegate_from(validator, amount);
+    }
+
+    /// Stop new delegations to a validator and undelegate everything it holds
+    pub fn deactivate_validator(&mut self, validator: PublicKey) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        if !self.validator_active.get(&validator).unwrap_or(false) {
+            self.env().revert(Error::ValidatorNotApproved);
+        }
+
+        self.validator_active.set(&validator, false);
+        let undelegated = self.undelegate_all(validator.clone());
+
+        self.env().emit_event(ValidatorDeactivated {
+            validator,
+            undelegated,
+        });
+    }
+
+    /// Drop a validator from the set, undelegating any remaining stake
+    pub fn remove_validator(&mut self, validator: PublicKey) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        let index = self
+            .validator_index(&validator)
+            .unwrap_or_else(|| self.env().revert(Error::ValidatorNotFound));
+
+        let undelegated = self.undelegate_all(validator.clone());
+
+        // Swap the last validator into the freed slot to keep the index dense
+        let last = self.validator_count.get_or_default() - 1;
+        if index != last {
+            if let Some(moved) = self.validators.get(&last) {
+                self.validators.set(&index, moved);
+            }
+        }
+        self.validator_count.set(last);
+        self.validator_active.set(&validator, false);
+        self.settings.target_weights.set(&validator, 0);
+
+        self.env().emit_event(ValidatorRemoved {
+            validator,
+            undelegated,
+        });
+    }
+
+    fn undelegate_all(&mut self, validator: PublicKey) -> U512 {
+        let delegated = self.validator_delegated.get(&validator).unwrap_or(U512::zero());
+        if delegated > U512::zero() {
+            self.undelegate_from(validator, delegated);
+        }
+        delegated
+    }
+
+    fn undelegate_from(&mut self, validator: PublicKey, amount: U512) {
+        let delegated = self.validator_delegated.get(&validator).unwrap_or(U512::zero());
         self.validator_delegated.set(&validator, delegated - amount);
 
         let pending_undel = self.pending_undelegations.get_or_default();
@@ -414,12 +868,45 @@ impl LiquidStake {
             self.env().undelegate(validator.clone(), amount);
         }
 
+        // Undelegated CSPR returns after unbonding, tracked as a withdrawal owned by this contract
+        let contract = self.env().self_address();
+        self.record_withdrawal(contract, amount);
+
         self.env().emit_event(AdminUndelegated {
             validator,
             amount,
         });
     }
 
+    /// Credit a matured undelegation back to available liquidity (callable by anyone)
+    pub fn complete_undelegation(&mut self, request_id: u64) {
+        let mut request = self
+            .withdrawal_requests
+            .get(&request_id)
+            .unwrap_or_else(|| self.env().revert(Error::WithdrawalNotFound));
+
+        if request.staker != self.env().self_address() {
+            self.env().revert(Error::NotWithdrawalOwner);
+        }
+        if request.claimed {
+            self.env().revert(Error::WithdrawalAlreadyClaimed);
+        }
+        if self.env().get_block_time() < request.expected_ready_at {
+            self.env().revert(Error::WithdrawalNotReady);
+        }
+
+        request.claimed = true;
+        self.withdrawal_requests.set(&request_id, request.clone());
+
+        let liquidity = self.available_liquidity.get_or_default();
+        self.available_liquidity.set(liquidity + request.cspr_amount);
+
+        self.env().emit_event(UndelegationCompleted {
+            request_id,
+            cspr_amount: request.cspr_amount,
+        });
+    }
+
     #[odra(payable)]
     pub fn admin_add_liquidity(&mut self) {
         let caller = self.env().caller();
@@ -450,19 +937,92 @@ impl LiquidStake {
             self.env().revert(Error::ValidatorAlreadyExists);
         }
 
-        let count = self.validator_count.get_or_default();
-        if count as usize >= MAX_VALIDATORS {
-            self.env().revert(Error::MaxValidatorsReached);
+        // A deactivated validator keeps its slot and is simply re-enabled
+        if self.validator_index(&validator).is_some() {
+            self.validator_active.set(&validator, true);
+        } else {
+            let count = self.validator_count.get_or_default();
+            if count as usize >= MAX_VALIDATORS {
+                self.env().revert(Error::MaxValidatorsReached);
+            }
+            self.validators.set(&count, validator.clone());
+            self.validator_active.set(&validator, true);
+            self.validator_delegated.set(&validator, U512::zero());
+            self.validator_count.set(count + 1);
         }
 
-        self.validators.set(&count, validator.clone());
-        self.validator_active.set(&validator, true);
-        self.validator_delegated.set(&validator, U512::zero());
-        self.validator_count.set(count + 1);
-
         self.env().emit_event(ValidatorAdded { validator });
     }
 
+    /// Set the unbonding delay in milliseconds for new withdrawal requests
+    pub fn set_unbonding_period(&mut self, unbonding_period_ms: u64) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        if !(MIN_UNBONDING_PERIOD_MS..=MAX_UNBONDING_PERIOD_MS).contains(&unbonding_period_ms) {
+            self.env().revert(Error::InvalidUnbondingPeriod);
+        }
+
+        self.settings.unbonding_period.set(unbonding_period_ms);
+        self.env().emit_event(UnbondingPeriodUpdated { unbonding_period_ms });
+    }
+
+    pub fn set_instant_unstake_fee(&mut self, fee_bps: u32) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        if fee_bps > MAX_INSTANT_UNSTAKE_FEE_BPS {
+            self.env().revert(Error::InvalidFee);
+        }
+
+        self.settings.instant_unstake_fee_bps.set(fee_bps);
+        self.env().emit_event(InstantUnstakeFeeUpdated { fee_bps });
+    }
+
+    pub fn set_protocol_fee(&mut self, fee_bps: u32) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        if fee_bps > MAX_PROTOCOL_FEE_BPS {
+            self.env().revert(Error::InvalidFee);
+        }
+
+        self.settings.protocol_fee_bps.set(fee_bps);
+        self.env().emit_event(ProtocolFeeUpdated { fee_bps });
+    }
+
+    /// Set the duel manager whose stake is kept out of delegation
+    pub fn set_duel_manager(&mut self, duel_manager: Address) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        self.settings.duel_manager.set(duel_manager);
+        self.env().emit_event(DuelManagerUpdated { duel_manager });
+    }
+
+    pub fn set_treasury(&mut self, treasury: Address) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        self.settings.treasury.set(treasury);
+        self.env().emit_event(TreasuryUpdated { treasury });
+    }
+
     #[odra(payable)]
     pub fn harvest_rewards(&mut self) {
         let caller = self.env().caller();
@@ -484,6 +1044,19 @@ impl LiquidStake {
 
         self.total_cspr_pool.set(pool + reward_amount);
 
+        // The protocol's cut is minted as stCSPR so it dilutes stakers instead of leaving the pool
+        let fee = reward_amount * U512::from(self.get_protocol_fee()) / U512::from(BPS_DENOMINATOR);
+        if fee > U512::zero() {
+            let stcspr_minted = self.fee_to_stcspr(fee);
+            let treasury = self.get_treasury().unwrap_or(owner);
+            self.token.raw_mint(&treasury, &stcspr_minted);
+            self.env().emit_event(ProtocolFeeCollected {
+                treasury,
+                cspr_amount: fee,
+                stcspr_minted,
+            });
+        }
+
         let new_rate = self.get_exchange_rate();
         self.env().emit_event(RewardsHarvested {
             amount: reward_amount,
@@ -493,11 +1066,11 @@ impl LiquidStake {
 
     // View functions
     pub fn get_stcspr_balance(&self, account: Address) -> U256 {
-        self.balances.get(&account).unwrap_or(U256::zero())
+        self.token.balance_of(&account)
     }
 
     pub fn get_total_supply(&self) -> U256 {
-        self.total_supply.get_or_default()
+        self.token.total_supply()
     }
 
     pub fn get_total_pool(&self) -> U512 {
@@ -512,6 +1085,57 @@ impl LiquidStake {
         self.pending_withdrawals.get_or_default()
     }
 
+    pub fn get_validator_delegated(&self, validator: PublicKey) -> U512 {
+        self.validator_delegated.get(&validator).unwrap_or(U512::zero())
+    }
+
+    pub fn get_validators(&self) -> Vec<ValidatorInfo> {
+        self.all_validators()
+            .into_iter()
+            .map(|validator| ValidatorInfo {
+                delegated: self.validator_delegated.get(&validator).unwrap_or(U512::zero()),
+                active: self.validator_active.get(&validator).unwrap_or(false),
+                validator,
+            })
+            .collect()
+    }
+
+    pub fn get_unbonding_period(&self) -> u64 {
+        self.settings.unbonding_period.get_or_default()
+    }
+
+    pub fn get_instant_unstake_fee(&self) -> u32 {
+        self.settings.instant_unstake_fee_bps.get_or_default()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.settings.paused.get_or_default()
+    }
+
+    pub fn get_guardian(&self) -> Option<Address> {
+        self.settings.guardian.get()
+    }
+
+    pub fn get_protocol_fee(&self) -> u32 {
+        self.settings.protocol_fee_bps.get_or_default()
+    }
+
+    pub fn get_treasury(&self) -> Option<Address> {
+        self.settings.treasury.get()
+    }
+
+    pub fn get_duel_manager(&self) -> Option<Address> {
+        self.settings.duel_manager.get()
+    }
+
+    pub fn get_allocation_mode(&self) -> AllocationMode {
+        self.settings.allocation_mode.get().unwrap_or(AllocationMode::EqualWeight)
+    }
+
+    pub fn get_target_weight(&self, validator: PublicKey) -> u32 {
+        self.settings.target_weights.get(&validator).unwrap_or(0)
+    }
+
     pub fn is_validator_active(&self, validator: PublicKey) -> bool {
         self.validator_active.get(&validator).unwrap_or(false)
     }
@@ -520,18 +1144,79 @@ impl LiquidStake {
         match self.withdrawal_requests.get(&request_id) {
             Some(request) => {
                 !request.claimed &&
-                self.env().get_block_time() >= request.request_block + UNBONDING_BLOCKS
+                self.env().get_block_time() >= request.expected_ready_at
             }
             None => false,
         }
     }
 
+    /// Get a page of a user's withdrawal requests, oldest first
+    pub fn get_user_withdrawals(&self, user: Address, offset: u64, limit: u64) -> Vec<WithdrawalInfo> {
+        let count = self.user_request_count.get(&user).unwrap_or(0);
+        let end = offset.saturating_add(limit).min(count);
+        (offset..end)
+            .filter_map(|index| self.user_requests.get(&(user, index)))
+            .filter_map(|request_id| {
+                self.withdrawal_requests.get(&request_id).map(|request| WithdrawalInfo {
+                    request_id,
+                    cspr_amount: request.cspr_amount,
+                    ready_at: request.expected_ready_at,
+                    claimed: request.claimed,
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_user_withdrawal_count(&self, user: Address) -> u64 {
+        self.user_request_count.get(&user).unwrap_or(0)
+    }
+
     pub fn get_withdrawal_amount(&self, request_id: u64) -> U512 {
         match self.withdrawal_requests.get(&request_id) {
             Some(request) => request.cspr_amount,
             None => U512::zero(),
         }
     }
+
+    // Duel entry fees are unstaked within minutes, so the duel manager's stake stays liquid
+    fn reserved_liquidity(&self) -> U512 {
+        let duel_stake = match self.settings.duel_manager.get() {
+            Some(duel_manager) => self.stcspr_to_cspr(self.token.balance_of(&duel_manager)),
+            None => U512::zero(),
+        };
+        self.pending_withdrawals.get_or_default() + duel_stake
+    }
+
+    fn record_withdrawal(&mut self, staker: Address, cspr_amount: U512) -> u64 {
+        let request_id = self.next_request_id.get_or_default();
+        self.next_request_id.set(request_id + 1);
+
+        let requested_at = self.env().get_block_time();
+        let request = WithdrawalRequest {
+            staker,
+            cspr_amount,
+            requested_at,
+            expected_ready_at: requested_at + self.get_unbonding_period(),
+            claimed: false,
+        };
+        self.withdrawal_requests.set(&request_id, request);
+
+        let user_count = self.user_request_count.get(&staker).unwrap_or(0);
+        self.user_requests.set(&(staker, user_count), request_id);
+        self.user_request_count.set(&staker, user_count + 1);
+
+        request_id
+    }
+
+    fn all_validators(&self) -> Vec<PublicKey> {
+        (0..self.validator_count.get_or_default())
+            .filter_map(|index| self.validators.get(&index))
+            .collect()
+    }
+
+    fn validator_index(&self, validator: &PublicKey) -> Option<u8> {
+        (0..self.validator_count.get_or_default()).find(|index| self.validators.get(index).as_ref() == Some(validator))
+    }
 }
 
 // ============================================================================
@@ -554,3 +1239,571 @@ fn u256_to_u512(value: U256) -> U512 {
     value.to_little_endian(&mut bytes);
     U512::from_little_endian(&bytes)
 }
+
+// Split `deployable` across validators given as (validator, currently delegated, weight).
+// Each allocation closes the gap between a validator's current delegation
+// and its weighted share of the total delegated stake.
+// New validators are skipped when their share is below MIN_DELEGATION.
+fn plan_allocation(validators: &[(PublicKey, U512, U512)], deployable: U512) -> Vec<(PublicKey, U512)> {
+    let total_weight = validators.iter().fold(U512::zero(), |acc, (_, _, weight)| acc + *weight);
+    if total_weight == U512::zero() || deployable == U512::zero() {
+        return Vec::new();
+    }
+
+    let total_delegated = validators.iter().fold(U512::zero(), |acc, (_, delegated, _)| acc + *delegated);
+    let target_total = total_delegated + deployable;
+    let gaps: Vec<U512> = validators
+        .iter()
+        .map(|(_, delegated, weight)| (target_total * *weight / total_weight).saturating_sub(*delegated))
+        .collect();
+    let total_gap = gaps.iter().fold(U512::zero(), |acc, gap| acc + *gap);
+    if total_gap == U512::zero() {
+        return Vec::new();
+    }
+
+    validators
+        .iter()
+        .zip(gaps.iter())
+        .map(|((validator, delegated, _), gap)| (validator.clone(), *delegated, deployable * *gap / total_gap))
+        .filter(|(_, delegated, amount)| {
+            *amount > U512::zero() && (*delegated > U512::zero() || *amount >= U512::from(MIN_DELEGATION))
+        })
+        .map(|(validator, _, amount)| (validator, amount))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, HostEnv, HostRef};
+
+    const CSPR: u64 = 1_000_000_000;
+
+    fn setup() -> (HostEnv, LiquidStakeHostRef) {
+        let env = odra_test::env();
+        let mut staking = LiquidStake::deploy(&env, LiquidStakeInitArgs { owner: env.get_account(0) });
+        staking.add_validator(env.get_validator(0));
+        (env, staking)
+    }
+
+    #[test]
+    fn stake_mints_at_exchange_rate() {
+        let (env, staking) = setup();
+        let validator = env.get_validator(0);
+
+        env.set_caller(env.get_account(1));
+        let first = staking.with_tokens(U512::from(100 * CSPR)).stake(validator.clone(), U512::from(100 * CSPR));
+        assert_eq!(first, U256::from(100 * CSPR));
+
+        // 10% rewards lift the rate to 1.1 CSPR per stCSPR
+        env.set_caller(env.get_account(0));
+        staking.with_tokens(U512::from(10 * CSPR)).harvest_rewards();
+        assert_eq!(staking.get_exchange_rate(), U512::from(1_100_000_000u64));
+
+        env.set_caller(env.get_account(2));
+        let second = staking.with_tokens(U512::from(110 * CSPR)).stake(validator.clone(), U512::from(110 * CSPR));
+        assert_eq!(second, U256::from(100 * CSPR));
+        assert_eq!(staking.get_exchange_rate(), U512::from(1_100_000_000u64));
+        assert!(env.emitted_event(
+            &staking,
+            Staked {
+                staker: env.get_account(2),
+                validator,
+                cspr_amount: U512::from(110 * CSPR),
+                stcspr_minted: U256::from(100 * CSPR),
+            }
+        ));
+    }
+
+    #[test]
+    fn stcspr_is_a_transferable_cep18_token() {
+        let (env, mut staking) = setup();
+        let (alice, bob, carol) = (env.get_account(1), env.get_account(2), env.get_account(3));
+        assert_eq!(staking.symbol(), "stCSPR".to_string());
+        assert_eq!(staking.decimals(), 9);
+
+        env.set_caller(alice);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        staking.transfer(&bob, &U256::from(4 * CSPR));
+        staking.approve(&carol, &U256::from(CSPR));
+
+        env.set_caller(carol);
+        staking.transfer_from(&alice, &carol, &U256::from(CSPR));
+
+        assert_eq!(staking.balance_of(&alice), U256::from(5 * CSPR));
+        assert_eq!(staking.balance_of(&bob), U256::from(4 * CSPR));
+        assert_eq!(staking.balance_of(&carol), U256::from(CSPR));
+        assert_eq!(staking.allowance(&alice, &carol), U256::zero());
+        assert_eq!(staking.total_supply(), U256::from(10 * CSPR));
+
+        // Transferred stCSPR can be unstaked by its new holder
+        env.set_caller(bob);
+        staking.request_unstake(U512::from(4 * CSPR));
+        assert_eq!(staking.get_stcspr_balance(bob), U256::zero());
+    }
+
+    #[test]
+    fn rebalance_splits_liquidity_equally() {
+        let (env, mut staking) = setup();
+        staking.add_validator(env.get_validator(1));
+        assert_eq!(staking.try_rebalance(), Err(Error::NothingToDelegate.into()));
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(2_000 * CSPR)).stake(env.get_validator(0), U512::from(2_000 * CSPR));
+        assert_eq!(staking.rebalance(), U512::from(2_000 * CSPR));
+
+        assert_eq!(staking.get_validator_delegated(env.get_validator(0)), U512::from(1_000 * CSPR));
+        assert_eq!(staking.get_validator_delegated(env.get_validator(1)), U512::from(1_000 * CSPR));
+        assert_eq!(staking.get_available_liquidity(), U512::zero());
+    }
+
+    #[test]
+    fn rebalance_follows_target_weights_and_minimum() {
+        let (env, mut staking) = setup();
+        staking.add_validator(env.get_validator(1));
+        staking.add_validator(env.get_validator(2));
+        staking.set_allocation_mode(AllocationMode::TargetWeight);
+        staking.set_target_weight(env.get_validator(0), 6_000);
+        staking.set_target_weight(env.get_validator(1), 3_500);
+        staking.set_target_weight(env.get_validator(2), 500);
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(2_000 * CSPR)).stake(env.get_validator(0), U512::from(2_000 * CSPR));
+        staking.rebalance();
+
+        // Validator 2's 100 CSPR share is below MIN_DELEGATION and stays liquid
+        assert_eq!(staking.get_validator_delegated(env.get_validator(0)), U512::from(1_200 * CSPR));
+        assert_eq!(staking.get_validator_delegated(env.get_validator(1)), U512::from(700 * CSPR));
+        assert_eq!(staking.get_validator_delegated(env.get_validator(2)), U512::zero());
+        assert_eq!(staking.get_available_liquidity(), U512::from(100 * CSPR));
+    }
+
+    #[test]
+    fn delegate_on_stake_keeps_pending_withdrawals_liquid() {
+        let (env, mut staking) = setup();
+        staking.set_delegate_on_stake(true);
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(1_000 * CSPR)).stake(env.get_validator(0), U512::from(1_000 * CSPR));
+        assert_eq!(staking.get_validator_delegated(env.get_validator(0)), U512::from(1_000 * CSPR));
+
+        env.set_caller(env.get_account(0));
+        staking.with_tokens(U512::from(100 * CSPR)).admin_add_liquidity();
+        env.set_caller(env.get_account(1));
+        staking.request_unstake(U512::from(100 * CSPR));
+        assert_eq!(staking.try_rebalance(), Err(Error::NothingToDelegate.into()));
+        assert_eq!(staking.get_available_liquidity(), U512::from(100 * CSPR));
+    }
+
+    #[test]
+    fn delegate_on_stake_keeps_duel_manager_stake_liquid() {
+        let (env, mut staking) = setup();
+        let duel_manager = env.get_account(3);
+        staking.set_delegate_on_stake(true);
+        staking.set_duel_manager(duel_manager);
+        assert!(env.emitted_event(&staking, DuelManagerUpdated { duel_manager }));
+        assert_eq!(staking.get_duel_manager(), Some(duel_manager));
+
+        env.set_caller(duel_manager);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(1_000 * CSPR)).stake(env.get_validator(0), U512::from(1_000 * CSPR));
+        assert_eq!(staking.get_validator_delegated(env.get_validator(0)), U512::from(1_000 * CSPR));
+        assert_eq!(staking.get_available_liquidity(), U512::from(10 * CSPR));
+        assert_eq!(
+            staking.try_instant_unstake(U512::from(CSPR)),
+            Err(Error::InsufficientLiquidity.into())
+        );
+
+        // Entry fees can be unstaked and claimed without waiting on an undelegation
+        env.set_caller(duel_manager);
+        let request_id = staking.request_unstake(U512::from(10 * CSPR));
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        staking.claim(request_id);
+        assert_eq!(staking.get_available_liquidity(), U512::zero());
+    }
+
+    #[test]
+    fn deactivated_validator_is_undelegated_and_skipped() {
+        let (env, mut staking) = setup();
+        let (first, second) = (env.get_validator(0), env.get_validator(1));
+        staking.add_validator(second.clone());
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(2_000 * CSPR)).stake(first.clone(), U512::from(2_000 * CSPR));
+        staking.rebalance();
+
+        env.set_caller(env.get_account(0));
+        staking.deactivate_validator(first.clone());
+        assert!(env.emitted_event(
+            &staking,
+            ValidatorDeactivated {
+                validator: first.clone(),
+                undelegated: U512::from(1_000 * CSPR),
+            }
+        ));
+        assert_eq!(staking.get_validator_delegated(first.clone()), U512::zero());
+        assert_eq!(
+            staking.try_admin_delegate(first.clone(), U512::from(1_000 * CSPR)),
+            Err(Error::ValidatorNotApproved.into())
+        );
+
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            staking.with_tokens(U512::from(CSPR)).try_stake(first.clone(), U512::from(CSPR)),
+            Err(Error::ValidatorNotApproved.into())
+        );
+        staking.with_tokens(U512::from(1_000 * CSPR)).stake(second.clone(), U512::from(1_000 * CSPR));
+        staking.rebalance();
+        assert_eq!(staking.get_validator_delegated(second.clone()), U512::from(2_000 * CSPR));
+
+        // Re-adding reactivates the existing slot
+        env.set_caller(env.get_account(0));
+        staking.add_validator(first.clone());
+        assert_eq!(
+            staking.get_validators(),
+            vec![
+                ValidatorInfo { validator: first, delegated: U512::zero(), active: true },
+                ValidatorInfo { validator: second, delegated: U512::from(2_000 * CSPR), active: true },
+            ]
+        );
+    }
+
+    #[test]
+    fn completed_undelegation_returns_to_liquidity() {
+        let (env, mut staking) = setup();
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(1_000 * CSPR)).stake(env.get_validator(0), U512::from(1_000 * CSPR));
+        staking.rebalance();
+
+        env.set_caller(env.get_account(0));
+        staking.admin_undelegate(env.get_validator(0), U512::from(400 * CSPR));
+        assert_eq!(staking.get_available_liquidity(), U512::zero());
+
+        // The undelegation is queued under the contract's own address
+        let request = &staking.get_user_withdrawals(staking.address(), 0, 1)[0];
+        assert_eq!(request.cspr_amount, U512::from(400 * CSPR));
+        assert_eq!(
+            staking.try_complete_undelegation(request.request_id),
+            Err(Error::WithdrawalNotReady.into())
+        );
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        env.set_caller(env.get_account(2));
+        staking.complete_undelegation(request.request_id);
+        assert_eq!(staking.get_available_liquidity(), U512::from(400 * CSPR));
+        assert!(env.emitted_event(
+            &staking,
+            UndelegationCompleted { request_id: request.request_id, cspr_amount: U512::from(400 * CSPR) }
+        ));
+        assert_eq!(
+            staking.try_complete_undelegation(request.request_id),
+            Err(Error::WithdrawalAlreadyClaimed.into())
+        );
+
+        // User withdrawals cannot be credited as undelegations
+        env.set_caller(env.get_account(1));
+        let user_request = staking.request_unstake(U512::from(CSPR));
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        assert_eq!(
+            staking.try_complete_undelegation(user_request),
+            Err(Error::NotWithdrawalOwner.into())
+        );
+    }
+
+    #[test]
+    fn remove_validator_compacts_index() {
+        let (env, mut staking) = setup();
+        staking.add_validator(env.get_validator(1));
+        staking.add_validator(env.get_validator(2));
+
+        staking.remove_validator(env.get_validator(0));
+        let remaining: Vec<PublicKey> = staking.get_validators().into_iter().map(|info| info.validator).collect();
+        assert_eq!(remaining, vec![env.get_validator(2), env.get_validator(1)]);
+        assert!(!staking.is_validator_active(env.get_validator(0)));
+        assert_eq!(
+            staking.try_remove_validator(env.get_validator(0)),
+            Err(Error::ValidatorNotFound.into())
+        );
+
+        staking.remove_validator(env.get_validator(1));
+        staking.add_validator(env.get_validator(0));
+        let remaining: Vec<PublicKey> = staking.get_validators().into_iter().map(|info| info.validator).collect();
+        assert_eq!(remaining, vec![env.get_validator(2), env.get_validator(0)]);
+
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            staking.try_deactivate_validator(env.get_validator(2)),
+            Err(Error::Unauthorized.into())
+        );
+    }
+
+    #[test]
+    fn user_withdrawals_are_paginated() {
+        let (env, mut staking) = setup();
+        let (alice, bob) = (env.get_account(1), env.get_account(2));
+        env.set_caller(alice);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        let first = staking.request_unstake(U512::from(CSPR));
+        let requested_at = env.block_time();
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        staking.claim(first);
+        let second = staking.request_unstake(U512::from(2 * CSPR));
+        staking.request_unstake(U512::from(3 * CSPR));
+
+        assert_eq!(staking.get_user_withdrawal_count(alice), 3);
+        assert_eq!(staking.get_user_withdrawal_count(bob), 0);
+        assert_eq!(
+            staking.get_user_withdrawals(alice, 0, 2),
+            vec![
+                WithdrawalInfo {
+                    request_id: first,
+                    cspr_amount: U512::from(CSPR),
+                    ready_at: requested_at + DEFAULT_UNBONDING_PERIOD_MS,
+                    claimed: true,
+                },
+                WithdrawalInfo {
+                    request_id: second,
+                    cspr_amount: U512::from(2 * CSPR),
+                    ready_at: requested_at + 2 * DEFAULT_UNBONDING_PERIOD_MS,
+                    claimed: false,
+                },
+            ]
+        );
+        assert_eq!(staking.get_user_withdrawals(alice, 2, 10).len(), 1);
+        assert!(staking.get_user_withdrawals(alice, 5, 10).is_empty());
+        assert!(staking.get_user_withdrawals(bob, 0, 10).is_empty());
+    }
+
+    #[test]
+    fn claim_waits_for_unbonding_period() {
+        let (env, mut staking) = setup();
+        let staker = env.get_account(1);
+        env.set_caller(staker);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        let request_id = staking.request_unstake(U512::from(4 * CSPR));
+        let ready_at = env.block_time() + DEFAULT_UNBONDING_PERIOD_MS;
+        assert_eq!(staking.get_user_withdrawals(staker, 0, 1)[0].ready_at, ready_at);
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS - 1);
+        assert!(!staking.is_withdrawal_ready(request_id));
+        assert_eq!(staking.try_claim(request_id), Err(Error::WithdrawalNotReady.into()));
+
+        env.advance_block_time(1);
+        assert!(staking.is_withdrawal_ready(request_id));
+        let balance_before = env.balance_of(&staker);
+        staking.claim(request_id);
+        assert_eq!(env.balance_of(&staker), balance_before + U512::from(4 * CSPR));
+    }
+
+    #[test]
+    fn unbonding_period_is_bounded_and_applies_to_new_requests() {
+        let (env, mut staking) = setup();
+        assert_eq!(staking.get_unbonding_period(), DEFAULT_UNBONDING_PERIOD_MS);
+        assert_eq!(
+            staking.try_set_unbonding_period(MIN_UNBONDING_PERIOD_MS - 1),
+            Err(Error::InvalidUnbondingPeriod.into())
+        );
+        assert_eq!(
+            staking.try_set_unbonding_period(MAX_UNBONDING_PERIOD_MS + 1),
+            Err(Error::InvalidUnbondingPeriod.into())
+        );
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        let early = staking.request_unstake(U512::from(CSPR));
+        assert_eq!(
+            staking.try_set_unbonding_period(MIN_UNBONDING_PERIOD_MS),
+            Err(Error::Unauthorized.into())
+        );
+
+        env.set_caller(env.get_account(0));
+        staking.set_unbonding_period(MIN_UNBONDING_PERIOD_MS);
+        assert!(env.emitted_event(
+            &staking,
+            UnbondingPeriodUpdated { unbonding_period_ms: MIN_UNBONDING_PERIOD_MS }
+        ));
+
+        env.set_caller(env.get_account(1));
+        let late = staking.request_unstake(U512::from(CSPR));
+        env.advance_block_time(MIN_UNBONDING_PERIOD_MS);
+        assert!(staking.is_withdrawal_ready(late));
+        assert!(!staking.is_withdrawal_ready(early));
+    }
+
+    #[test]
+    fn instant_unstake_charges_fee_to_pool() {
+        let (env, mut staking) = setup();
+        let (alice, bob) = (env.get_account(1), env.get_account(2));
+        env.set_caller(env.get_account(0));
+        staking.set_instant_unstake_fee(100);
+
+        env.set_caller(alice);
+        staking.with_tokens(U512::from(100 * CSPR)).stake(env.get_validator(0), U512::from(100 * CSPR));
+        env.set_caller(bob);
+        staking.with_tokens(U512::from(100 * CSPR)).stake(env.get_validator(0), U512::from(100 * CSPR));
+
+        let balance_before = env.balance_of(&bob);
+        let paid = staking.instant_unstake(U512::from(100 * CSPR));
+        assert_eq!(paid, U512::from(99 * CSPR));
+        assert_eq!(env.balance_of(&bob), balance_before + U512::from(99 * CSPR));
+        assert!(env.emitted_event(
+            &staking,
+            InstantUnstaked {
+                staker: bob,
+                stcspr_amount: U512::from(100 * CSPR),
+                cspr_amount: U512::from(99 * CSPR),
+                fee: U512::from(CSPR),
+            }
+        ));
+
+        // The 1 CSPR fee now belongs to the remaining stakers
+        assert_eq!(staking.get_total_pool(), U512::from(101 * CSPR));
+        assert_eq!(staking.get_exchange_rate(), U512::from(1_010_000_000u64));
+        assert_eq!(staking.get_available_liquidity(), U512::from(101 * CSPR));
+    }
+
+    #[test]
+    fn instant_unstake_requires_buffer_and_bounded_fee() {
+        let (env, mut staking) = setup();
+        assert_eq!(staking.get_instant_unstake_fee(), DEFAULT_INSTANT_UNSTAKE_FEE_BPS);
+        assert_eq!(
+            staking.try_set_instant_unstake_fee(MAX_INSTANT_UNSTAKE_FEE_BPS + 1),
+            Err(Error::InvalidFee.into())
+        );
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(1_000 * CSPR)).stake(env.get_validator(0), U512::from(1_000 * CSPR));
+        staking.rebalance();
+        assert_eq!(
+            staking.try_instant_unstake(U512::from(CSPR)),
+            Err(Error::InsufficientLiquidity.into())
+        );
+        assert_eq!(
+            staking.try_instant_unstake(U512::from(2_000 * CSPR)),
+            Err(Error::InsufficientStCsprBalance.into())
+        );
+    }
+
+    #[test]
+    fn harvest_mints_protocol_fee_to_treasury() {
+        let (env, mut staking) = setup();
+        let treasury = env.get_account(5);
+        assert_eq!(staking.get_treasury(), Some(env.get_account(0)));
+        assert_eq!(staking.try_set_protocol_fee(MAX_PROTOCOL_FEE_BPS + 1), Err(Error::InvalidFee.into()));
+        staking.set_protocol_fee(1_000);
+        staking.set_treasury(treasury);
+        assert_eq!(staking.get_protocol_fee(), 1_000);
+
+        env.set_caller(env.get_account(1));
+        staking.with_tokens(U512::from(100 * CSPR)).stake(env.get_validator(0), U512::from(100 * CSPR));
+        assert_eq!(staking.try_set_treasury(env.get_account(1)), Err(Error::Unauthorized.into()));
+
+        // 10% of a 10 CSPR harvest goes to the treasury, stakers keep 9 CSPR
+        env.set_caller(env.get_account(0));
+        staking.with_tokens(U512::from(10 * CSPR)).harvest_rewards();
+        assert!(env.emitted_event(
+            &staking,
+            ProtocolFeeCollected {
+                treasury,
+                cspr_amount: U512::from(CSPR),
+                stcspr_minted: U256::from(917_431_192u64),
+            }
+        ));
+        assert_eq!(staking.get_total_pool(), U512::from(110 * CSPR));
+        assert_eq!(staking.get_exchange_rate(), U512::from(1_090_000_000u64));
+
+        env.set_caller(treasury);
+        staking.request_unstake(U512::from(917_431_192u64));
+        let request = &staking.get_user_withdrawals(treasury, 0, 1)[0];
+        assert_eq!(request.cspr_amount, U512::from(999_999_999u64));
+    }
+
+    #[test]
+    fn pause_blocks_user_flows_and_optionally_allows_claims() {
+        let (env, mut staking) = setup();
+        let (guardian, staker) = (env.get_account(4), env.get_account(1));
+        staking.set_guardian(guardian);
+        assert!(env.emitted_event(&staking, GuardianUpdated { guardian }));
+        assert_eq!(staking.get_guardian(), Some(guardian));
+
+        env.set_caller(staker);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        let request_id = staking.request_unstake(U512::from(2 * CSPR));
+        assert_eq!(staking.try_pause(), Err(Error::Unauthorized.into()));
+
+        env.set_caller(guardian);
+        staking.pause();
+        assert!(staking.is_paused());
+        assert!(env.emitted_event(&staking, Paused { by: guardian }));
+        assert_eq!(staking.try_unpause(), Err(Error::Unauthorized.into()));
+
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        env.set_caller(staker);
+        assert_eq!(
+            staking.with_tokens(U512::from(CSPR)).try_stake(env.get_validator(0), U512::from(CSPR)),
+            Err(Error::ContractPaused.into())
+        );
+        assert_eq!(staking.try_request_unstake(U512::from(CSPR)), Err(Error::ContractPaused.into()));
+        assert_eq!(staking.try_claim(request_id), Err(Error::ContractPaused.into()));
+
+        env.set_caller(env.get_account(0));
+        assert_eq!(
+            staking.try_admin_delegate(env.get_validator(0), U512::from(MIN_DELEGATION)),
+            Err(Error::ContractPaused.into())
+        );
+        staking.set_claims_while_paused(true);
+
+        env.set_caller(staker);
+        staking.claim(request_id);
+
+        env.set_caller(env.get_account(0));
+        staking.unpause();
+        assert!(!staking.is_paused());
+        assert!(env.emitted_event(&staking, Unpaused { by: env.get_account(0) }));
+        env.set_caller(staker);
+        staking.request_unstake(U512::from(CSPR));
+    }
+
+    #[test]
+    fn pause_lets_duel_manager_unstake_and_claim() {
+        let (env, mut staking) = setup();
+        let duel_manager = env.get_account(3);
+        staking.set_duel_manager(duel_manager);
+
+        env.set_caller(duel_manager);
+        staking.with_tokens(U512::from(10 * CSPR)).stake(env.get_validator(0), U512::from(10 * CSPR));
+        env.set_caller(env.get_account(0));
+        staking.pause();
+
+        // Deposits stay blocked, but entry fees of running duels can be unwound
+        env.set_caller(duel_manager);
+        assert_eq!(
+            staking.with_tokens(U512::from(CSPR)).try_stake(env.get_validator(0), U512::from(CSPR)),
+            Err(Error::ContractPaused.into())
+        );
+        let request_id = staking.request_unstake(U512::from(10 * CSPR));
+        env.advance_block_time(DEFAULT_UNBONDING_PERIOD_MS);
+        let balance_before = env.balance_of(&duel_manager);
+        staking.claim(request_id);
+        assert_eq!(env.balance_of(&duel_manager), balance_before + U512::from(10 * CSPR));
+    }
+
+    #[test]
+    fn stake_validates_validator_and_value() {
+        let (env, mut staking) = setup();
+        env.set_caller(env.get_account(1));
+        assert_eq!(
+            staking.with_tokens(U512::from(CSPR)).try_stake(env.get_validator(1), U512::from(CSPR)),
+            Err(Error::ValidatorNotApproved.into())
+        );
+        assert_eq!(
+            staking.try_stake(env.get_validator(0), U512::from(CSPR)),
+            Err(Error::InsufficientBalance.into())
+        );
+        assert_eq!(
+            staking.try_stake(env.get_validator(0), U512::zero()),
+            Err(Error::ZeroAmount.into())
+        );
+    }
+}
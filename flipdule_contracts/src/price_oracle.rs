@@ -4,13 +4,22 @@
 use odra::prelude::*;
 use odra::casper_types::U512;
 
+// Observations retained per NFT before the ring buffer wraps
+const PRICE_HISTORY_SIZE: u64 = 64;
+
 #[odra::module]
 pub struct FlipDuelPriceOracle {
     nft_prices: Mapping<String, PriceData>,
     authorized_updaters: List<Address>,
     owner: Var<Address>,
     min_update_interval: Var<u64>,
+    max_price_age: Var<u64>,
     total_price_updates: Var<u64>,
+    min_quorum: Var<u32>,
+    price_rounds: Mapping<String, u64>,
+    round_submissions: Mapping<(String, u64), Vec<PriceSubmission>>,
+    price_history: Mapping<(String, u64), PriceObservation>,
+    history_count: Mapping<String, u64>,
 }
 
 #[odra::odra_type]
@@ -22,6 +31,22 @@ pub struct PriceData {
     pub update_count: u32,
 }
 
+/// A published price and when it took effect
+#[odra::odra_type]
+pub struct PriceObservation {
+    pub price: U512,
+    pub timestamp: u64,
+}
+
+/// One updater's price for an NFT in a round, kept for audit
+#[odra::odra_type]
+pub struct PriceSubmission {
+    pub updater: Address,
+    pub price: U512,
+    pub source: String,
+    pub timestamp: u64,
+}
+
 #[odra::module]
 impl FlipDuelPriceOracle {
     /// Initialize the price oracle
@@ -29,7 +54,9 @@ impl FlipDuelPriceOracle {
         let caller = self.env().caller();
         self.owner.set(caller);
         self.min_update_interval.set(30000); // 30 seconds default
+        self.max_price_age.set(300000); // 5 minutes default
         self.total_price_updates.set(0);
+        self.min_quorum.set(1);
         
         // Add owner as first authorized updater
         self.authorized_updaters.push(caller);
@@ -37,7 +64,8 @@ impl FlipDuelPriceOracle {
         self.env().emit_event(OracleInitialized { owner: caller });
     }
 
-    /// Update a single NFT price
+    /// Submit a price for an NFT in the current round
+    /// The canonical price is set to the median once the round reaches quorum
     pub fn update_price(&mut self, nft_id: String, price: U512, source: String) {
         let caller = self.env().caller();
         self.require_authorized(caller);
@@ -47,41 +75,7 @@ impl FlipDuelPriceOracle {
         }
 
         let current_time = self.env().get_block_time();
-        
-        // Check if update interval has passed
-        if let Some(existing) = self.nft_prices.get(&nft_id) {
-            let min_interval = self.min_update_interval.get_or_default();
-            if current_time < existing.last_updated + min_interval {
-                self.env().revert(Error::UpdateTooFrequent);
-            }
-        }
-
-        let update_count = self.nft_prices
-            .get(&nft_id)
-            .map(|p| p.update_count + 1)
-            .unwrap_or(1);
-
-        let price_data = PriceData {
-            nft_id: nft_id.clone(),
-            price,
-            last_updated: current_time,
-            source: source.clone(),
-            update_count,
-        };
-
-        self.nft_prices.set(&nft_id, price_data);
-
-        // Increment global counter
-        let total = self.total_price_updates.get_or_default();
-        self.total_price_updates.set(total + 1);
-
-        self.env().emit_event(PriceUpdated {
-            nft_id,
-            price,
-            timestamp: current_time,
-            source,
-            updater: caller,
-        });
+        self.submit_price(caller, nft_id, price, source, current_time);
     }
 
     /// Batch update multiple NFT prices
@@ -104,27 +98,10 @@ impl FlipDuelPriceOracle {
             self.env().revert(Error::InvalidPrice);
         }
 
-            let update_count = self.nft_prices
-                .get(&nft_id)
-                .map(|p| p.update_count + 1)
-                .unwrap_or(1);
-
-            let price_data = PriceData {
-                nft_id: nft_id.clone(),
-                price,
-                last_updated: current_time,
-                source,
-                update_count,
-            };
-
-            self.nft_prices.set(&nft_id, price_data);
+            self.submit_price(caller, nft_id, price, source, current_time);
             updated_count += 1;
         }
 
-        // Update global counter
-        let total = self.total_price_updates.get_or_default();
-        self.total_price_updates.set(total + updated_count as u64);
-
         self.env().emit_event(BatchUpdateCompleted {
             count: updated_count,
             timestamp: current_time,
@@ -140,6 +117,85 @@ impl FlipDuelPriceOracle {
             .unwrap_or(U512::zero())
     }
 
+    /// Get price for an NFT, reverting if it is missing or older than max_age_ms
+    pub fn get_fresh_price(&self, nft_id: String, max_age_ms: u64) -> U512 {
+        let data = match self.nft_prices.get(&nft_id) {
+            Some(data) => data,
+            None => self.env().revert(Error::PriceNotFound),
+        };
+
+        let current_time = self.env().get_block_time();
+        if current_time.saturating_sub(data.last_updated) > max_age_ms {
+            self.env().revert(Error::StalePrice);
+        }
+
+        data.price
+    }
+
+    /// Check if an NFT price is within the configured max age
+    pub fn is_price_fresh(&self, nft_id: String) -> bool {
+        let max_age = self.max_price_age.get_or_default();
+        self.nft_prices
+            .get(&nft_id)
+            .is_some_and(|data| self.env().get_block_time().saturating_sub(data.last_updated) <= max_age)
+    }
+
+    /// Get retained observations with timestamps in [from, to], oldest first
+    pub fn get_price_history(&self, nft_id: String, from: u64, to: u64) -> Vec<PriceObservation> {
+        self.retained_history(&nft_id)
+            .into_iter()
+            .filter(|observation| observation.timestamp >= from && observation.timestamp <= to)
+            .collect()
+    }
+
+    /// Get the time-weighted average price over [end_time - window_ms, end_time]
+    /// Each observation holds until the next one and the last one until end_time, with no
+    /// staleness check, the same way consumers value holdings on their last known price.
+    /// An NFT has no price before its first observation, so the average starts there.
+    /// Returns None without an observation by end_time, or when older observations
+    /// were overwritten and the retained history no longer covers the window
+    pub fn get_twap(&self, nft_id: String, end_time: u64, window_ms: u64) -> Option<U512> {
+        let history: Vec<PriceObservation> = self
+            .retained_history(&nft_id)
+            .into_iter()
+            .filter(|observation| observation.timestamp <= end_time)
+            .collect();
+        let latest = history.last()?.price;
+
+        let window_start = end_time.saturating_sub(window_ms);
+        let wrapped = self.history_count.get(&nft_id).unwrap_or(0) > PRICE_HISTORY_SIZE;
+        if wrapped && history[0].timestamp > window_start {
+            return None;
+        }
+
+        let mut weighted_sum = U512::zero();
+        let mut total_time = 0u64;
+
+        for (i, observation) in history.iter().enumerate() {
+            let end = history.get(i + 1).map(|next| next.timestamp).unwrap_or(end_time);
+            let start = observation.timestamp.max(window_start);
+            if end > start {
+                weighted_sum += observation.price * U512::from(end - start);
+                total_time += end - start;
+            }
+        }
+
+        if total_time == 0 {
+            return Some(latest);
+        }
+        Some(weighted_sum / U512::from(total_time))
+    }
+
+    /// Get the open round for an NFT
+    pub fn get_current_round(&self, nft_id: String) -> u64 {
+        self.price_rounds.get(&nft_id).unwrap_or(0)
+    }
+
+    /// Get every submission made in a round
+    pub fn get_round_submissions(&self, nft_id: String, round: u64) -> Vec<PriceSubmission> {
+        self.round_submissions.get(&(nft_id, round)).unwrap_or_default()
+    }
+
     /// Get complete price data for an NFT
     pub fn get_price_data(&self, nft_id: String) -> Option<PriceData> {
         self.nft_prices.get(&nft_id)
@@ -203,6 +259,7 @@ impl FlipDuelPriceOracle {
                         self.authorized_updaters.replace(i, last_val);
                     }
                 }
+                self.authorized_updaters.pop();
                 found = true;
                 break;
             }
@@ -211,6 +268,9 @@ impl FlipDuelPriceOracle {
         if !found {
             self.env().revert(Error::UpdaterNotFound);
         }
+        if self.authorized_updaters.len() < self.min_quorum.get_or_default() {
+            self.env().revert(Error::InvalidQuorum);
+        }
 
         self.env().emit_event(UpdaterRemoved { 
             updater,
@@ -242,6 +302,51 @@ impl FlipDuelPriceOracle {
         });
     }
 
+    /// Set how many distinct updaters must submit before a price is published
+    pub fn set_min_quorum(&mut self, quorum: u32) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+
+        if caller != owner {
+            self.env().revert(Error::OnlyOwner);
+        }
+        if quorum == 0 || quorum > self.authorized_updaters.len() {
+            self.env().revert(Error::InvalidQuorum);
+        }
+
+        let old_quorum = self.min_quorum.get_or_default();
+        self.min_quorum.set(quorum);
+
+        self.env().emit_event(QuorumUpdated {
+            old_quorum,
+            new_quorum: quorum,
+        });
+    }
+
+    /// Set the max age consumers should accept for a price
+    pub fn set_max_price_age(&mut self, max_age_ms: u64) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+
+        if caller != owner {
+            self.env().revert(Error::OnlyOwner);
+        }
+        if max_age_ms < 1000 {
+            self.env().revert(Error::InvalidMaxPriceAge);
+        }
+        if max_age_ms > 3600000 {
+            self.env().revert(Error::InvalidMaxPriceAge);
+        }
+
+        let old_max_age = self.max_price_age.get_or_default();
+        self.max_price_age.set(max_age_ms);
+
+        self.env().emit_event(MaxPriceAgeUpdated {
+            old_max_age,
+            new_max_age: max_age_ms,
+        });
+    }
+
     /// Transfer ownership
     pub fn transfer_ownership(&mut self, new_owner: Address) {
         let caller = self.env().caller();
@@ -300,6 +405,16 @@ impl FlipDuelPriceOracle {
         self.min_update_interval.get_or_default()
     }
 
+    /// Get number of submissions required to publish a price
+    pub fn get_min_quorum(&self) -> u32 {
+        self.min_quorum.get_or_default()
+    }
+
+    /// Get configured max price age
+    pub fn get_max_price_age(&self) -> u64 {
+        self.max_price_age.get_or_default()
+    }
+
     // ============== INTERNAL HELPERS ==============
 
     fn require_authorized(&self, address: Address) {
@@ -307,6 +422,112 @@ impl FlipDuelPriceOracle {
             self.env().revert(Error::NotAuthorized);
         }
     }
+
+    fn record_observation(&mut self, nft_id: &String, price: U512, timestamp: u64) {
+        let count = self.history_count.get(nft_id).unwrap_or(0);
+        self.price_history.set(&(nft_id.clone(), count % PRICE_HISTORY_SIZE), PriceObservation { price, timestamp });
+        self.history_count.set(nft_id, count + 1);
+    }
+
+    fn retained_history(&self, nft_id: &String) -> Vec<PriceObservation> {
+        let count = self.history_count.get(nft_id).unwrap_or(0);
+        (count.saturating_sub(PRICE_HISTORY_SIZE)..count)
+            .filter_map(|index| self.price_history.get(&(nft_id.clone(), index % PRICE_HISTORY_SIZE)))
+            .collect()
+    }
+
+    /// Record a submission and publish the median when the round reaches quorum
+    fn submit_price(&mut self, updater: Address, nft_id: String, price: U512, source: String, current_time: u64) {
+        let round = self.price_rounds.get(&nft_id).unwrap_or(0);
+        let round_key = (nft_id.clone(), round);
+        let mut submissions = self.round_submissions.get(&round_key).unwrap_or_default();
+
+        // Submissions older than the max price age stay stored for audit,
+        // but would publish a stale median so they no longer count
+        let max_age = self.max_price_age.get_or_default();
+        let is_fresh = |submission: &PriceSubmission| current_time.saturating_sub(submission.timestamp) <= max_age;
+
+        // A round opens at most once per update interval, whichever entry point submits
+        if !submissions.iter().any(is_fresh) {
+            if let Some(existing) = self.nft_prices.get(&nft_id) {
+                let min_interval = self.min_update_interval.get_or_default();
+                if current_time < existing.last_updated + min_interval {
+                    self.env().revert(Error::UpdateTooFrequent);
+                }
+            }
+        }
+
+        if submissions.iter().any(|submission| is_fresh(submission) && submission.updater == updater) {
+            self.env().revert(Error::AlreadySubmitted);
+        }
+
+        submissions.push(PriceSubmission {
+            updater,
+            price,
+            source: source.clone(),
+            timestamp: current_time,
+        });
+        self.round_submissions.set(&round_key, submissions.clone());
+
+        self.env().emit_event(PriceSubmitted {
+            nft_id: nft_id.clone(),
+            round,
+            price,
+            updater,
+        });
+
+        // Only fresh submissions from updaters that are still authorized count towards the quorum
+        let counted: Vec<U512> = submissions
+            .iter()
+            .filter(|submission| is_fresh(submission) && self.is_authorized(submission.updater))
+            .map(|submission| submission.price)
+            .collect();
+        if (counted.len() as u32) < self.min_quorum.get_or_default() {
+            return;
+        }
+
+        let source = if counted.len() == 1 { source } else { "median".to_string() };
+        let median = median_price(counted);
+        let update_count = self.nft_prices
+            .get(&nft_id)
+            .map(|p| p.update_count + 1)
+            .unwrap_or(1);
+
+        let price_data = PriceData {
+            nft_id: nft_id.clone(),
+            price: median,
+            last_updated: current_time,
+            source: source.clone(),
+            update_count,
+        };
+
+        self.nft_prices.set(&nft_id, price_data);
+        self.price_rounds.set(&nft_id, round + 1);
+        self.record_observation(&nft_id, median, current_time);
+
+        // Increment global counter
+        let total = self.total_price_updates.get_or_default();
+        self.total_price_updates.set(total + 1);
+
+        self.env().emit_event(PriceUpdated {
+            nft_id,
+            price: median,
+            timestamp: current_time,
+            source,
+            updater,
+        });
+    }
+}
+
+// Median of the submitted prices, averaging the middle pair for even counts
+fn median_price(mut prices: Vec<U512>) -> U512 {
+    prices.sort();
+    let mid = prices.len() / 2;
+    if prices.len() % 2 == 0 {
+        (prices[mid - 1] + prices[mid]) / U512::from(2)
+    } else {
+        prices[mid]
+    }
 }
 
 #[odra::odra_type]
@@ -333,6 +554,14 @@ pub struct PriceUpdated {
     pub updater: Address,
 }
 
+#[odra::event]
+pub struct PriceSubmitted {
+    pub nft_id: String,
+    pub round: u64,
+    pub price: U512,
+    pub updater: Address,
+}
+
 #[odra::event]
 pub struct BatchUpdateCompleted {
     pub count: u32,
@@ -358,6 +587,18 @@ pub struct IntervalUpdated {
     pub new_interval: u64,
 }
 
+#[odra::event]
+pub struct QuorumUpdated {
+    pub old_quorum: u32,
+    pub new_quorum: u32,
+}
+
+#[odra::event]
+pub struct MaxPriceAgeUpdated {
+    pub old_max_age: u64,
+    pub new_max_age: u64,
+}
+
 #[odra::event]
 pub struct OwnershipTransferred {
     pub previous_owner: Address,
@@ -376,4 +617,195 @@ pub enum Error {
     InvalidInterval,
     InvalidAddress,
     NotAuthorized,
-}
\ No newline at end of file
+    PriceNotFound,
+    StalePrice,
+    AlreadySubmitted,
+    InvalidQuorum,
+    InvalidMaxPriceAge,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::host::{Deployer, NoArgs};
+
+    #[test]
+    fn fresh_price_reverts_once_stale() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        assert_eq!(oracle.try_get_fresh_price("NFT1".to_string(), 60_000), Err(Error::PriceNotFound.into()));
+
+        oracle.update_price("NFT1".to_string(), U512::from(2_000_000_000u64), "market".to_string());
+        env.advance_block_time(60_000);
+        assert_eq!(oracle.get_fresh_price("NFT1".to_string(), 60_000), U512::from(2_000_000_000u64));
+        assert!(oracle.is_price_fresh("NFT1".to_string()));
+
+        env.advance_block_time(1);
+        assert_eq!(oracle.try_get_fresh_price("NFT1".to_string(), 60_000), Err(Error::StalePrice.into()));
+
+        oracle.set_max_price_age(30_000);
+        assert!(!oracle.is_price_fresh("NFT1".to_string()));
+        assert_eq!(oracle.try_set_max_price_age(999), Err(Error::InvalidMaxPriceAge.into()));
+        assert_eq!(oracle.try_set_max_price_age(3_600_001), Err(Error::InvalidMaxPriceAge.into()));
+    }
+
+    #[test]
+    fn price_is_median_of_quorum_submissions() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        let (second, third) = (env.get_account(1), env.get_account(2));
+        oracle.add_authorized_updater(second);
+        oracle.add_authorized_updater(third);
+        assert_eq!(oracle.try_set_min_quorum(4), Err(Error::InvalidQuorum.into()));
+        oracle.set_min_quorum(3);
+
+        oracle.update_price("NFT1".to_string(), U512::from(100u64), "a".to_string());
+        assert_eq!(
+            oracle.try_update_price("NFT1".to_string(), U512::from(200u64), "a".to_string()),
+            Err(Error::AlreadySubmitted.into())
+        );
+        env.set_caller(second);
+        oracle.update_price("NFT1".to_string(), U512::from(1_000_000u64), "b".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::zero());
+
+        // A single outlier cannot move the published price
+        env.set_caller(third);
+        oracle.update_price("NFT1".to_string(), U512::from(110u64), "c".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::from(110u64));
+        assert_eq!(oracle.get_price_data("NFT1".to_string()).unwrap().source, "median".to_string());
+        assert_eq!(oracle.get_current_round("NFT1".to_string()), 1);
+        let submissions = oracle.get_round_submissions("NFT1".to_string(), 0);
+        assert_eq!(
+            submissions.iter().map(|submission| (submission.updater, submission.price)).collect::<Vec<_>>(),
+            vec![
+                (env.get_account(0), U512::from(100u64)),
+                (second, U512::from(1_000_000u64)),
+                (third, U512::from(110u64)),
+            ]
+        );
+    }
+
+    #[test]
+    fn removed_updater_loses_access() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        let updater = env.get_account(1);
+        oracle.add_authorized_updater(updater);
+        oracle.set_min_quorum(2);
+        assert_eq!(median_price(vec![U512::from(30u64), U512::from(10u64)]), U512::from(20u64));
+
+        // Removal may not leave fewer updaters than the quorum needs
+        assert_eq!(oracle.try_remove_authorized_updater(updater), Err(Error::InvalidQuorum.into()));
+        oracle.set_min_quorum(1);
+        oracle.remove_authorized_updater(updater);
+        assert!(!oracle.is_authorized(updater));
+        assert_eq!(oracle.get_authorized_updaters(), vec![env.get_account(0)]);
+        env.set_caller(updater);
+        assert_eq!(
+            oracle.try_update_price("NFT1".to_string(), U512::from(10u64), "a".to_string()),
+            Err(Error::NotAuthorized.into())
+        );
+    }
+
+    #[test]
+    fn rounds_skip_expired_and_unauthorized_submissions() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        let (second, third) = (env.get_account(1), env.get_account(2));
+        oracle.add_authorized_updater(second);
+        oracle.add_authorized_updater(third);
+        oracle.set_min_quorum(2);
+
+        // An expired submission stops counting and its updater may submit again,
+        // but it stays stored with the round
+        oracle.update_price("NFT1".to_string(), U512::from(100u64), "a".to_string());
+        env.advance_block_time(oracle.get_max_price_age() + 1);
+        env.set_caller(second);
+        oracle.update_price("NFT1".to_string(), U512::from(300u64), "b".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::zero());
+        env.set_caller(env.get_account(0));
+        oracle.update_price("NFT1".to_string(), U512::from(200u64), "a".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::from(250u64));
+        let prices: Vec<U512> = oracle
+            .get_round_submissions("NFT1".to_string(), 0)
+            .iter()
+            .map(|submission| submission.price)
+            .collect();
+        assert_eq!(prices, vec![U512::from(100u64), U512::from(300u64), U512::from(200u64)]);
+
+        // A removed updater's submission no longer counts towards the quorum
+        env.advance_block_time(oracle.get_min_update_interval());
+        env.set_caller(second);
+        oracle.update_price("NFT1".to_string(), U512::from(1_000u64), "b".to_string());
+        env.set_caller(env.get_account(0));
+        oracle.remove_authorized_updater(second);
+        oracle.update_price("NFT1".to_string(), U512::from(400u64), "a".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::from(250u64));
+        env.set_caller(third);
+        oracle.update_price("NFT1".to_string(), U512::from(500u64), "c".to_string());
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::from(450u64));
+    }
+
+    #[test]
+    fn batch_updates_respect_update_interval() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        oracle.update_price("NFT1".to_string(), U512::from(100u64), "a".to_string());
+        assert_eq!(
+            oracle.try_batch_update_prices(vec![("NFT1".to_string(), U512::from(200u64), "a".to_string())]),
+            Err(Error::UpdateTooFrequent.into())
+        );
+
+        env.advance_block_time(oracle.get_min_update_interval());
+        oracle.batch_update_prices(vec![("NFT1".to_string(), U512::from(200u64), "a".to_string())]);
+        assert_eq!(oracle.get_price("NFT1".to_string()), U512::from(200u64));
+    }
+
+    #[test]
+    fn twap_weights_prices_by_time_held() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        env.advance_block_time(1_000);
+        let start = env.block_time();
+        oracle.update_price("NFT1".to_string(), U512::from(100u64), "a".to_string());
+        env.advance_block_time(90_000);
+        oracle.update_price("NFT1".to_string(), U512::from(400u64), "a".to_string());
+        env.advance_block_time(30_000);
+
+        let now = env.block_time();
+        assert_eq!(oracle.get_twap("NFT1".to_string(), now, 120_000), Some(U512::from(175u64)));
+        assert_eq!(oracle.get_twap("NFT1".to_string(), now, 60_000), Some(U512::from(250u64)));
+        assert_eq!(oracle.get_twap("NFT1".to_string(), now, 1_000_000), Some(U512::from(175u64)));
+        assert_eq!(oracle.get_twap("NFT1".to_string(), now, 0), Some(U512::from(400u64)));
+        assert_eq!(oracle.get_twap("NFT2".to_string(), now, 60_000), None);
+
+        // The window ends at end_time, so later observations do not count
+        assert_eq!(oracle.get_twap("NFT1".to_string(), start + 90_000, 120_000), Some(U512::from(100u64)));
+        assert_eq!(oracle.get_twap("NFT1".to_string(), start - 1, 120_000), None);
+
+        let history = oracle.get_price_history("NFT1".to_string(), start + 1, start + 90_000);
+        assert_eq!(history, vec![PriceObservation { price: U512::from(400u64), timestamp: start + 90_000 }]);
+    }
+
+    #[test]
+    fn price_history_keeps_latest_observations() {
+        let env = odra_test::env();
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        oracle.set_min_update_interval(1_000);
+        for price in 1..=(PRICE_HISTORY_SIZE + 6) {
+            oracle.update_price("NFT1".to_string(), U512::from(price), "a".to_string());
+            env.advance_block_time(1_000);
+        }
+
+        let history = oracle.get_price_history("NFT1".to_string(), 0, u64::MAX);
+        assert_eq!(history.len() as u64, PRICE_HISTORY_SIZE);
+        assert_eq!(history[0].price, U512::from(7u64));
+        assert_eq!(history.last().unwrap().price, U512::from(PRICE_HISTORY_SIZE + 6));
+        assert!(history.windows(2).all(|pair| pair[0].timestamp < pair[1].timestamp));
+
+        // Windows reaching past the overwritten observations have no TWAP
+        let now = env.block_time();
+        assert!(oracle.get_twap("NFT1".to_string(), now, 10_000).is_some());
+        assert_eq!(oracle.get_twap("NFT1".to_string(), now, 100_000), None);
+    }
+}
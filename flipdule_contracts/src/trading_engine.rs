@@ -3,13 +3,24 @@
 
 use odra::prelude::*;
 use odra::casper_types::U512;
+use odra::ContractRef;
+use crate::price_oracle::{FlipDuelPriceOracleContractRef, PriceData};
+
+// Default max price age when prices are pushed by an account instead of an oracle contract,
+// matching the oracle's own default
+const DEFAULT_MAX_PRICE_AGE_MS: u64 = 300_000;
 
 #[odra::module]
 pub struct FlipDuelTradingEngine {
     portfolios: Mapping<(u64, Address), Portfolio>,
-    trade_history: Mapping<(u64, Address), List<Trade>>,
-    nft_prices: Mapping<String, U512>,
+    trade_history: Mapping<(u64, Address, u32), Trade>,
+    nft_prices: Mapping<String, CachedPrice>,
+    collection_nfts: Mapping<String, Vec<String>>,
+    duel_nfts: Mapping<u64, Vec<String>>,
+    duel_end_times: Mapping<u64, u64>,
     price_oracle: Var<Address>,
+    max_price_age: Var<u64>,
+    twap_window: Var<u64>,
     duel_manager: Var<Address>,
     owner: Var<Address>,
     total_trades: Var<u64>,
@@ -23,6 +34,13 @@ pub struct Portfolio {
     pub nfts_owned: Vec<NFTHolding>,
     pub initial_value: U512,
     pub trades_count: u32,
+    pub last_trade_time: u64,
+}
+
+#[odra::odra_type]
+pub struct CachedPrice {
+    pub price: U512,
+    pub updated_at: u64,
 }
 
 #[odra::odra_type]
@@ -61,9 +79,36 @@ impl FlipDuelTradingEngine {
     pub fn init(&mut self) {
         let caller = self.env().caller();
         self.owner.set(caller);
+        self.max_price_age.set(DEFAULT_MAX_PRICE_AGE_MS);
         self.total_trades.set(0);
     }
 
+    /// Set max age of a price usable for trades and valuation (owner only)
+    /// Applies while prices are pushed by an account, an oracle contract's own setting wins otherwise
+    pub fn set_max_price_age(&mut self, max_age_ms: u64) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        if !(1_000..=3_600_000).contains(&max_age_ms) {
+            self.env().revert(Error::InvalidMaxPriceAge);
+        }
+        self.max_price_age.set(max_age_ms);
+    }
+
+    /// Set the oracle TWAP window used to rank portfolios, zero ranks on spot prices (owner only)
+    pub fn set_twap_window(&mut self, window_ms: u64) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+        self.twap_window.set(window_ms);
+
+        self.env().emit_event(TwapWindowUpdated { window_ms });
+    }
+
     /// Set price oracle address (owner only)
     pub fn set_price_oracle(&mut self, price_oracle_addr: Address) {
         let caller = self.env().caller();
@@ -100,6 +145,7 @@ impl FlipDuelTradingEngine {
             nfts_owned: Vec::new(),
             initial_value: starting_balance,
             trades_count: 0,
+            last_trade_time: self.env().get_block_time(),
         };
 
         self.portfolios.set(&(duel_id, player), portfolio);
@@ -111,15 +157,55 @@ impl FlipDuelTradingEngine {
         });
     }
 
+    /// Register the NFTs of a collection that duels on it may trade (owner only)
+    pub fn set_collection_nfts(&mut self, collection: String, nft_ids: Vec<String>) {
+        let caller = self.env().caller();
+        let owner = self.owner.get().unwrap();
+        if caller != owner {
+            self.env().revert(Error::Unauthorized);
+        }
+
+        let count = nft_ids.len() as u32;
+        self.collection_nfts.set(&collection, nft_ids);
+
+        self.env().emit_event(CollectionNftsUpdated {
+            collection,
+            count,
+        });
+    }
+
+    /// Snapshot a collection's NFTs as the allow-list for a duel, trading closes at end_time
+    pub fn register_duel_nfts(&mut self, duel_id: u64, collection: String, end_time: u64) {
+        let caller = self.env().caller();
+        let duel_manager = self.duel_manager.get().unwrap();
+
+        if caller != duel_manager {
+            self.env().revert(Error::OnlyDuelManager);
+        }
+
+        let nft_ids = self.collection_nfts.get(&collection).unwrap_or_default();
+        if nft_ids.is_empty() {
+            self.env().revert(Error::UnknownCollection);
+        }
+
+        self.duel_nfts.set(&duel_id, nft_ids);
+        self.duel_end_times.set(&duel_id, end_time);
+    }
+
     /// Execute a buy trade
     pub fn execute_buy(&mut self, duel_id: u64, nft_id: String) {
         let caller = self.env().caller();
         let mut portfolio = self.portfolios
             .get(&(duel_id, caller))
             .expect("FlipDuel: Portfolio not found");
+        self.require_duel_running(duel_id);
+
+        if !self.is_nft_tradable(duel_id, nft_id.clone()) {
+            self.env().revert(Error::NftNotTradable);
+        }
 
         // Get current price from oracle
-        let price = self.get_nft_price(&nft_id);
+        let price = self.get_fresh_nft_price(&nft_id);
         
         if portfolio.cspr_balance < price {
             self.env().revert(Error::InsufficientBalance);
@@ -128,6 +214,15 @@ impl FlipDuelTradingEngine {
             self.env().revert(Error::AlreadyOwnsNFT);
         }
 
+        // Record trade in history, indexed by the player's trade counter
+        let trade = Trade {
+            nft_id: nft_id.clone(),
+            trade_type: TradeType::Buy,
+            price,
+            timestamp: self.env().get_block_time(),
+        };
+        self.trade_history.set(&(duel_id, caller, portfolio.trades_count), trade);
+
         // Update portfolio
         portfolio.cspr_balance = portfolio.cspr_balance - price;
         portfolio.nfts_owned.push(NFTHolding {
@@ -136,21 +231,10 @@ impl FlipDuelTradingEngine {
             purchase_time: self.env().get_block_time(),
         });
         portfolio.trades_count += 1;
+        portfolio.last_trade_time = self.env().get_block_time();
 
         self.portfolios.set(&(duel_id, caller), portfolio);
 
-        // Record trade in history
-        let _trade = Trade {
-            nft_id: nft_id.clone(),
-            trade_type: TradeType::Buy,
-            price,
-            timestamp: self.env().get_block_time(),
-        };
-
-        // Trade history disabled - List in Mapping not supported
-        // history.push(trade);
-        // self.trade_history.set(&(duel_id, caller), history);
-
         // Update global trade counter
         let total = self.total_trades.get_or_default();
         self.total_trades.set(total + 1);
@@ -171,6 +255,7 @@ impl FlipDuelTradingEngine {
         let mut portfolio = self.portfolios
             .get(&(duel_id, caller))
             .expect("FlipDuel: Portfolio not found");
+        self.require_duel_running(duel_id);
 
         // Find and remove NFT from holdings
         let nft_index = portfolio
@@ -182,25 +267,23 @@ impl FlipDuelTradingEngine {
         let _nft_holding = portfolio.nfts_owned.remove(nft_index);
         
         // Get current price from oracle
-        let price = self.get_nft_price(&nft_id);
+        let price = self.get_fresh_nft_price(&nft_id);
 
-        // Update balance
-        portfolio.cspr_balance = portfolio.cspr_balance + price;
-        portfolio.trades_count += 1;
-        
-        self.portfolios.set(&(duel_id, caller), portfolio);
-
-        // Record trade in history
-        let _trade = Trade {
+        // Record trade in history, indexed by the player's trade counter
+        let trade = Trade {
             nft_id: nft_id.clone(),
             trade_type: TradeType::Sell,
             price,
             timestamp: self.env().get_block_time(),
         };
+        self.trade_history.set(&(duel_id, caller, portfolio.trades_count), trade);
+
+        // Update balance
+        portfolio.cspr_balance = portfolio.cspr_balance + price;
+        portfolio.trades_count += 1;
+        portfolio.last_trade_time = self.env().get_block_time();
 
-        // Trade history disabled - List in Mapping not supported
-        // history.push(trade);
-        // self.trade_history.set(&(duel_id, caller), history);
+        self.portfolios.set(&(duel_id, caller), portfolio);
 
         // Update global trade counter
         let total = self.total_trades.get_or_default();
@@ -216,7 +299,15 @@ impl FlipDuelTradingEngine {
         });
     }
 
-    /// Get total portfolio value (CSPR + NFTs at current prices)
+    // Trades after the end would be ranked on a TWAP window that is already known
+    fn require_duel_running(&self, duel_id: u64) {
+        let end_time = self.duel_end_times.get(&duel_id).unwrap_or_default();
+        if self.env().get_block_time() >= end_time {
+            self.env().revert(Error::DuelEnded);
+        }
+    }
+
+    /// Get total portfolio value (CSPR + NFTs at current prices), reverting if a price is stale
     pub fn get_portfolio_value(&self, duel_id: u64, player: Address) -> U512 {
         let portfolio = self.portfolios
             .get(&(duel_id, player))
@@ -226,7 +317,7 @@ impl FlipDuelTradingEngine {
 
         // Add current value of all NFTs
         for nft in &portfolio.nfts_owned {
-            let current_price = self.get_nft_price(&nft.nft_id);
+            let current_price = self.get_fresh_nft_price(&nft.nft_id);
             total_value = total_value + current_price;
         }
 
@@ -240,30 +331,7 @@ impl FlipDuelTradingEngine {
             .expect("FlipDuel: Portfolio not found");
 
         let current_value = self.get_portfolio_value(duel_id, player);
-        let initial_value = portfolio.initial_value;
-
-        if initial_value == U512::zero() {
-            return 0;
-        }
-
-        // Calculate percentage gain: ((current - initial) / initial) * 100
-        // Using fixed-point arithmetic for precision
-        let is_positive = current_value >= initial_value;
-        
-        let diff = if is_positive {
-            current_value - initial_value
-        } else {
-            initial_value - current_value
-        };
-
-        let gain_pct = (diff * U512::from(10000)) / initial_value;
-        let gain_pct_u64 = gain_pct.as_u64();
-
-        if is_positive {
-            (gain_pct_u64 / 100) as i32
-        } else {
-            -((gain_pct_u64 / 100) as i32)
-        }
+        gain_percentage(current_value, portfolio.initial_value)
     }
 
     /// Get portfolio statistics
@@ -284,10 +352,39 @@ impl FlipDuelTradingEngine {
         }
     }
 
-    /// Get complete trade history for a player in a duel
-    pub fn get_trade_history(&self, _duel_id: u64, _player: Address) -> Vec<Trade> {
-        // Trade history disabled - List in Mapping not supported
-        Vec::new()
+    /// Get a page of trade history for a player in a duel (oldest first)
+    pub fn get_trade_history(&self, duel_id: u64, player: Address, offset: u32, limit: u32) -> Vec<Trade> {
+        let trades_count = self.portfolios
+            .get(&(duel_id, player))
+            .map(|portfolio| portfolio.trades_count)
+            .unwrap_or(0);
+
+        let end = offset.saturating_add(limit).min(trades_count);
+        (offset..end)
+            .filter_map(|index| self.trade_history.get(&(duel_id, player, index)))
+            .collect()
+    }
+
+    /// Check whether an NFT is on a duel's allow-list
+    pub fn is_nft_tradable(&self, duel_id: u64, nft_id: String) -> bool {
+        self.duel_nfts
+            .get(&duel_id)
+            .is_some_and(|nft_ids| nft_ids.contains(&nft_id))
+    }
+
+    /// Get the NFTs tradable in a duel
+    pub fn get_duel_nfts(&self, duel_id: u64) -> Vec<String> {
+        self.duel_nfts.get(&duel_id).unwrap_or_default()
+    }
+
+    /// Get the time trading closes for a duel
+    pub fn get_duel_end_time(&self, duel_id: u64) -> u64 {
+        self.duel_end_times.get(&duel_id).unwrap_or_default()
+    }
+
+    /// Get the NFTs registered for a collection
+    pub fn get_collection_nfts(&self, collection: String) -> Vec<String> {
+        self.collection_nfts.get(&collection).unwrap_or_default()
     }
 
     /// Get portfolio details
@@ -295,20 +392,24 @@ impl FlipDuelTradingEngine {
         self.portfolios.get(&(duel_id, player))
     }
 
-    /// Get leaderboard for a duel (all players sorted by gain)
-    pub fn get_leaderboard(&self, duel_id: u64, players: Vec<Address>) -> Vec<LeaderboardEntry> {
+    /// Get leaderboard for a duel (all players sorted by portfolio value)
+    /// Ranks on last known prices, even stale ones, so an ended duel can always close
+    /// TWAP rankings use the window ending at end_time, capped at the current block time
+    pub fn get_leaderboard(&self, duel_id: u64, players: Vec<Address>, end_time: u64) -> Vec<LeaderboardEntry> {
+        let end_time = end_time.min(self.env().get_block_time());
         let mut leaderboard: Vec<LeaderboardEntry> = players
             .iter()
             .filter_map(|player| {
                 if let Some(portfolio) = self.portfolios.get(&(duel_id, *player)) {
-                    let current_value = self.get_portfolio_value(duel_id, *player);
-                    let gain = self.calculate_gain(duel_id, *player);
+                    let current_value = self.ranking_value(&portfolio, end_time);
+                    let gain = gain_percentage(current_value, portfolio.initial_value);
                     
                     Some(LeaderboardEntry {
                         player: *player,
                         current_value,
                         gain_percentage: gain,
                         trades_count: portfolio.trades_count,
+                        last_trade_time: portfolio.last_trade_time,
                     })
                 } else {
                     None
@@ -316,23 +417,74 @@ impl FlipDuelTradingEngine {
             })
             .collect();
 
-        // Sort by gain percentage (descending)
-        leaderboard.sort_by(|a, b| b.gain_percentage.cmp(&a.gain_percentage));
+        // Sort by portfolio value (descending), whole-number gain percentages are for display only
+        leaderboard.sort_by(|a, b| b.current_value.cmp(&a.current_value));
         
         leaderboard
     }
 
     // ============== PRICE MANAGEMENT ==============
 
-    /// Get NFT price (from cache or oracle)
+    /// Value a portfolio for ranking, using the oracle TWAP up to end_time when a window is set
+    /// so a price move in the final seconds of a duel cannot decide it.
+    /// NFTs without oracle history covering the window fall back to their last known price
+    fn ranking_value(&self, portfolio: &Portfolio, end_time: u64) -> U512 {
+        let window = self.twap_window.get_or_default();
+        let oracle = self.price_oracle.get().filter(|address| address.is_contract());
+        match oracle {
+            Some(oracle) if window > 0 => {
+                let oracle = FlipDuelPriceOracleContractRef::new(self.env(), oracle);
+                portfolio.nfts_owned.iter().fold(portfolio.cspr_balance, |total, nft| {
+                    let price = oracle
+                        .get_twap(nft.nft_id.clone(), end_time, window)
+                        .unwrap_or_else(|| self.get_nft_price(&nft.nft_id));
+                    total + price
+                })
+            }
+            _ => self.last_known_value(portfolio),
+        }
+    }
+
+    /// Value a portfolio on last known prices without a staleness check, used only for ranking
+    fn last_known_value(&self, portfolio: &Portfolio) -> U512 {
+        portfolio
+            .nfts_owned
+            .iter()
+            .fold(portfolio.cspr_balance, |total, nft| total + self.get_nft_price(&nft.nft_id))
+    }
+
+    /// Get the last known NFT price, however old
     fn get_nft_price(&self, nft_id: &str) -> U512 {
-        // Check local cache first
-        if let Some(price) = self.nft_prices.get(&nft_id.to_string()) {
-            return price;
+        self.nft_price_data(nft_id).0
+    }
+
+    /// Get NFT price for a trade or valuation, reverting if it is older than the max price age
+    fn get_fresh_nft_price(&self, nft_id: &str) -> U512 {
+        let (price, updated_at) = self.nft_price_data(nft_id);
+        let age = self.env().get_block_time().saturating_sub(updated_at);
+        if age > self.get_max_price_age() {
+            self.env().revert(Error::StalePrice);
+        }
+        price
+    }
+
+    /// Get NFT price and its update time (from oracle contract or cache)
+    fn nft_price_data(&self, nft_id: &str) -> (U512, u64) {
+        // A wired oracle contract is the source of truth,
+        // otherwise use prices pushed through update_nft_price
+        match self.oracle_price_data(nft_id) {
+            Some(data) => (data.price, data.last_updated),
+            None => match self.nft_prices.get(&nft_id.to_string()) {
+                Some(cached) => (cached.price, cached.updated_at),
+                None => self.env().revert(Error::PriceUnavailable),
+            },
         }
-        
-        // Default price if not found
-        U512::from(1_000_000_000u64) // 1 CSPR default
+    }
+
+    /// Read published price data when the oracle address is a contract
+    fn oracle_price_data(&self, nft_id: &str) -> Option<PriceData> {
+        let oracle = self.price_oracle.get().filter(|address| address.is_contract())?;
+        FlipDuelPriceOracleContractRef::new(self.env(), oracle).get_price_data(nft_id.to_string())
     }
 
     /// Update NFT price (called by oracle)
@@ -344,7 +496,8 @@ impl FlipDuelTradingEngine {
             self.env().revert(Error::OnlyOracle);
         }
         
-        self.nft_prices.set(&nft_id, price);
+        let updated_at = self.env().get_block_time();
+        self.nft_prices.set(&nft_id, CachedPrice { price, updated_at });
 
         self.env().emit_event(PriceUpdated { 
             nft_id, 
@@ -362,9 +515,10 @@ impl FlipDuelTradingEngine {
         }
 
         let count = updates.len() as u32;
+        let updated_at = self.env().get_block_time();
 
         for (nft_id, price) in updates {
-            self.nft_prices.set(&nft_id, price);
+            self.nft_prices.set(&nft_id, CachedPrice { price, updated_at });
         }
 
         self.env().emit_event(BatchPricesUpdated {
@@ -372,18 +526,58 @@ impl FlipDuelTradingEngine {
         });
     }
 
+    /// Get TWAP window used for ranking, zero means spot prices
+    pub fn get_twap_window(&self) -> u64 {
+        self.twap_window.get_or_default()
+    }
+
+    /// Get max price age accepted for trades and valuation,
+    /// read from the oracle contract when one is wired
+    pub fn get_max_price_age(&self) -> u64 {
+        match self.price_oracle.get().filter(|address| address.is_contract()) {
+            Some(oracle) => FlipDuelPriceOracleContractRef::new(self.env(), oracle).get_max_price_age(),
+            None => self.max_price_age.get_or_default(),
+        }
+    }
+
     /// Get total platform trades
     pub fn get_total_trades(&self) -> u64 {
         self.total_trades.get_or_default()
     }
 }
 
+// Percentage gain: ((current - initial) / initial) * 100
+fn gain_percentage(current_value: U512, initial_value: U512) -> i32 {
+    if initial_value == U512::zero() {
+        return 0;
+    }
+
+    // Using fixed-point arithmetic for precision
+    let is_positive = current_value >= initial_value;
+
+    let diff = if is_positive {
+        current_value - initial_value
+    } else {
+        initial_value - current_value
+    };
+
+    let gain_pct = (diff * U512::from(10000)) / initial_value;
+    let gain_pct_u64 = gain_pct.as_u64();
+
+    if is_positive {
+        (gain_pct_u64 / 100) as i32
+    } else {
+        -((gain_pct_u64 / 100) as i32)
+    }
+}
+
 #[odra::odra_type]
 pub struct LeaderboardEntry {
     pub player: Address,
     pub current_value: U512,
     pub gain_percentage: i32,
     pub trades_count: u32,
+    pub last_trade_time: u64,
 }
 
 // ============== EVENTS ==============
@@ -416,6 +610,17 @@ pub struct BatchPricesUpdated {
     pub count: u32,
 }
 
+#[odra::event]
+pub struct TwapWindowUpdated {
+    pub window_ms: u64,
+}
+
+#[odra::event]
+pub struct CollectionNftsUpdated {
+    pub collection: String,
+    pub count: u32,
+}
+
 #[odra::odra_error]
 pub enum Error {
     OnlyDuelManager,
@@ -423,4 +628,231 @@ pub enum Error {
     AlreadyOwnsNFT,
     OnlyOracle,
     Unauthorized,
-}
\ No newline at end of file
+    PriceUnavailable,
+    NftNotTradable,
+    UnknownCollection,
+    StalePrice,
+    InvalidMaxPriceAge,
+    DuelEnded,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_oracle::FlipDuelPriceOracle;
+    use odra::host::{Deployer, HostEnv, NoArgs};
+
+    const DUEL_END: u64 = 60 * 60 * 1000;
+
+    // Deployer stands in for both the duel manager and the price oracle
+    fn setup() -> (HostEnv, FlipDuelTradingEngineHostRef) {
+        let env = odra_test::env();
+        let deployer = env.get_account(0);
+        let mut engine = FlipDuelTradingEngine::deploy(&env, NoArgs);
+        engine.set_duel_manager(deployer);
+        engine.set_price_oracle(deployer);
+        engine.set_collection_nfts(
+            "collection".to_string(),
+            vec!["NFT1".to_string(), "NFT2".to_string(), "NFT3".to_string()],
+        );
+        engine.register_duel_nfts(1, "collection".to_string(), DUEL_END);
+        engine.initialize_portfolio(1, env.get_account(1), U512::from(10_000_000_000u64));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_000_000_000u64));
+        engine.update_nft_price("NFT2".to_string(), U512::from(2_000_000_000u64));
+        (env, engine)
+    }
+
+    #[test]
+    fn trade_history_matches_trades_count() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+        engine.execute_buy(1, "NFT2".to_string());
+        engine.execute_sell(1, "NFT1".to_string());
+
+        let history = engine.get_trade_history(1, player, 0, 10);
+        assert_eq!(history.len() as u32, engine.get_portfolio(1, player).unwrap().trades_count);
+        assert_eq!(
+            history.iter().map(|trade| (trade.nft_id.as_str(), trade.trade_type.clone())).collect::<Vec<_>>(),
+            vec![("NFT1", TradeType::Buy), ("NFT2", TradeType::Buy), ("NFT1", TradeType::Sell)]
+        );
+        assert_eq!(history[1].price, U512::from(2_000_000_000u64));
+    }
+
+    #[test]
+    fn trade_history_pagination() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        env.set_caller(player);
+        for _ in 0..3 {
+            engine.execute_buy(1, "NFT1".to_string());
+            engine.execute_sell(1, "NFT1".to_string());
+        }
+
+        let page = engine.get_trade_history(1, player, 2, 2);
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].trade_type, TradeType::Buy);
+        assert_eq!(page[1].trade_type, TradeType::Sell);
+        assert_eq!(engine.get_trade_history(1, player, 5, 10).len(), 1);
+        assert!(engine.get_trade_history(1, player, 6, 10).is_empty());
+        assert!(engine.get_trade_history(1, env.get_account(2), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn trades_use_prices_published_by_oracle_contract() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        oracle.update_price("NFT1".to_string(), U512::from(3_000_000_000u64), "market".to_string());
+        engine.set_price_oracle(oracle.address());
+
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+        assert_eq!(engine.get_trade_history(1, player, 0, 1)[0].price, U512::from(3_000_000_000u64));
+        assert_eq!(engine.get_portfolio_value(1, player), U512::from(10_000_000_000u64));
+
+        env.set_caller(env.get_account(0));
+        env.advance_block_time(30_000);
+        oracle.update_price("NFT1".to_string(), U512::from(4_000_000_000u64), "market".to_string());
+        assert_eq!(engine.get_portfolio_value(1, player), U512::from(11_000_000_000u64));
+
+        // Trades follow the oracle's max price age
+        oracle.set_max_price_age(60_000);
+        assert_eq!(engine.get_max_price_age(), 60_000);
+        env.advance_block_time(60_001);
+        env.set_caller(player);
+        assert_eq!(engine.try_execute_sell(1, "NFT1".to_string()), Err(Error::StalePrice.into()));
+    }
+
+    #[test]
+    fn buys_require_allow_listed_and_priced_nfts() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        assert_eq!(engine.get_duel_nfts(1).len(), 3);
+        assert!(!engine.is_nft_tradable(1, "NFT9".to_string()));
+        assert_eq!(
+            engine.try_register_duel_nfts(2, "unknown".to_string(), DUEL_END),
+            Err(Error::UnknownCollection.into())
+        );
+
+        env.set_caller(player);
+        assert_eq!(engine.try_execute_buy(1, "NFT9".to_string()), Err(Error::NftNotTradable.into()));
+        assert_eq!(engine.try_execute_buy(1, "NFT3".to_string()), Err(Error::PriceUnavailable.into()));
+        assert_eq!(engine.get_total_trades(), 0);
+
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT3".to_string(), U512::from(500_000_000u64));
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT3".to_string());
+        assert_eq!(engine.get_portfolio_value(1, player), U512::from(10_000_000_000u64));
+    }
+
+    #[test]
+    fn stale_prices_block_trades_and_valuation_but_not_ranking() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+
+        env.advance_block_time(engine.get_max_price_age() + 1);
+        assert_eq!(engine.try_execute_buy(1, "NFT2".to_string()), Err(Error::StalePrice.into()));
+        assert_eq!(engine.try_execute_sell(1, "NFT1".to_string()), Err(Error::StalePrice.into()));
+        assert_eq!(engine.try_get_portfolio_value(1, player), Err(Error::StalePrice.into()));
+        assert_eq!(engine.try_calculate_gain(1, player), Err(Error::StalePrice.into()));
+        assert_eq!(engine.try_get_portfolio_stats(1, player), Err(Error::StalePrice.into()));
+        assert_eq!(
+            engine.get_leaderboard(1, vec![player], env.block_time())[0].current_value,
+            U512::from(10_000_000_000u64)
+        );
+
+        env.set_caller(env.get_account(0));
+        engine.batch_update_prices(vec![("NFT1".to_string(), U512::from(1_500_000_000u64))]);
+        assert_eq!(engine.get_portfolio_value(1, player), U512::from(10_500_000_000u64));
+        env.set_caller(player);
+        engine.execute_sell(1, "NFT1".to_string());
+    }
+
+    #[test]
+    fn trades_stop_at_duel_end() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        assert_eq!(engine.get_duel_end_time(1), DUEL_END);
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+
+        env.advance_block_time(DUEL_END);
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_000_000_000u64));
+        engine.update_nft_price("NFT2".to_string(), U512::from(2_000_000_000u64));
+        env.set_caller(player);
+        assert_eq!(engine.try_execute_buy(1, "NFT2".to_string()), Err(Error::DuelEnded.into()));
+        assert_eq!(engine.try_execute_sell(1, "NFT1".to_string()), Err(Error::DuelEnded.into()));
+    }
+
+    #[test]
+    fn max_price_age_is_configurable_for_account_oracles() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        assert_eq!(engine.get_max_price_age(), DEFAULT_MAX_PRICE_AGE_MS);
+        assert_eq!(engine.try_set_max_price_age(999), Err(Error::InvalidMaxPriceAge.into()));
+        assert_eq!(engine.try_set_max_price_age(3_600_001), Err(Error::InvalidMaxPriceAge.into()));
+
+        engine.set_max_price_age(600_000);
+        assert_eq!(engine.get_max_price_age(), 600_000);
+        env.advance_block_time(DEFAULT_MAX_PRICE_AGE_MS + 1);
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+        assert_eq!(engine.try_set_max_price_age(60_000), Err(Error::Unauthorized.into()));
+    }
+
+    #[test]
+    fn leaderboard_orders_by_value_within_the_same_whole_percentage() {
+        let (env, mut engine) = setup();
+        let (first, second) = (env.get_account(1), env.get_account(2));
+        engine.initialize_portfolio(1, second, U512::from(10_000_000_000u64));
+        env.set_caller(second);
+        engine.execute_buy(1, "NFT1".to_string());
+        env.set_caller(env.get_account(0));
+        engine.update_nft_price("NFT1".to_string(), U512::from(1_050_000_000u64));
+
+        let leaderboard = engine.get_leaderboard(1, vec![first, second], env.block_time());
+        assert_eq!(leaderboard[0].player, second);
+        assert_eq!(leaderboard[0].current_value, U512::from(10_050_000_000u64));
+        assert_eq!(leaderboard[0].gain_percentage, leaderboard[1].gain_percentage);
+    }
+
+    #[test]
+    fn leaderboard_ranks_on_twap_when_configured() {
+        let (env, mut engine) = setup();
+        let player = env.get_account(1);
+        let mut oracle = FlipDuelPriceOracle::deploy(&env, NoArgs);
+        oracle.update_price("NFT1".to_string(), U512::from(1_000_000_000u64), "market".to_string());
+        engine.set_price_oracle(oracle.address());
+
+        // NFT2 is only priced in the engine cache and ranks on that price
+        env.set_caller(player);
+        engine.execute_buy(1, "NFT1".to_string());
+        engine.execute_buy(1, "NFT2".to_string());
+
+        env.set_caller(env.get_account(0));
+        env.advance_block_time(90_000);
+        let jump_time = env.block_time();
+        oracle.update_price("NFT1".to_string(), U512::from(4_000_000_000u64), "market".to_string());
+        env.advance_block_time(30_000);
+        let now = env.block_time();
+
+        assert_eq!(engine.get_leaderboard(1, vec![player], now)[0].current_value, U512::from(13_000_000_000u64));
+
+        engine.set_twap_window(120_000);
+        assert!(env.emitted_event(&engine, TwapWindowUpdated { window_ms: 120_000 }));
+        let entry = &engine.get_leaderboard(1, vec![player], now)[0];
+        assert_eq!(entry.current_value, U512::from(10_750_000_000u64));
+        assert_eq!(entry.gain_percentage, 7);
+        assert_eq!(engine.get_portfolio_value(1, player), U512::from(13_000_000_000u64));
+
+        // A duel that ended before the jump ranks on the window ending at its end time
+        let entry = &engine.get_leaderboard(1, vec![player], jump_time)[0];
+        assert_eq!(entry.current_value, U512::from(10_000_000_000u64));
+    }
+}